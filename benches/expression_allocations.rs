@@ -0,0 +1,114 @@
+//! Counts the allocations of building a deeply nested expression,
+//! compared to formatting its text at every step.
+//!
+//! Run with `cargo bench --bench expression_allocations`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use geogebra_types::{prelude::*, raw::ConstructionItem};
+
+/// Nesting depth of the built expression
+const DEPTH: usize = 1000;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting allocations and allocated bytes.
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Run `f`, returning its result with the allocations and bytes it took.
+fn measure<T>(f: impl FnOnce() -> T) -> (T, usize, usize) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+    let result = f();
+
+    (
+        result,
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        BYTES.load(Ordering::Relaxed) - bytes,
+    )
+}
+
+fn main() {
+    let mut ggb = Geogebra::new();
+    let x = ggb.add_slider("x", 1.0, (0.0, 2.0), 0.1);
+
+    let (nested, built, built_bytes) = measure(|| {
+        let mut exp = Numeric::from(&x);
+
+        for _ in 0..DEPTH {
+            exp = (exp + &x).sin() * 2;
+        }
+
+        exp
+    });
+
+    // The text is only put together when the expression is added.
+    let (_, added, added_bytes) = measure(|| ggb.add(Point::from((nested, 0)), ""));
+
+    // The same steps, formatting the text every time.
+    let (formatted, eager, eager_bytes) = measure(|| {
+        let label = Rc::new(String::from("elem0"));
+        let mut exp = Rc::clone(&label);
+
+        for _ in 0..DEPTH {
+            exp = Rc::new(format!("({exp}) + ({label})"));
+            exp = Rc::new(format!("sin({exp})"));
+            let two = Rc::new(format!("{} + 0i", 2.0));
+            exp = Rc::new(format!("({exp}) * ({two})"));
+        }
+
+        let zero = Rc::new(format!("{} + 0i", 0.0));
+        Rc::new(format!("(real({exp}), real({zero}))"))
+    });
+
+    let written = ggb
+        .raw()
+        .construction
+        .items
+        .iter()
+        .find_map(|item| match item {
+            ConstructionItem::Expression(expression) if expression.label == "elem1" => {
+                Some(expression.exp.as_str())
+            }
+            _ => None,
+        })
+        .expect("the expression was added");
+
+    assert_eq!(written, formatted.as_str(), "the output changed");
+
+    println!("depth {DEPTH}, {} bytes of text", written.len());
+    println!("nested:    {built:>6} allocations, {built_bytes:>10} bytes to build");
+    println!("           {added:>6} allocations, {added_bytes:>10} bytes to add");
+    println!("formatted: {eager:>6} allocations, {eager_bytes:>10} bytes to build");
+
+    assert!(
+        built + added < eager,
+        "building nested expressions should allocate less"
+    );
+}
@@ -4,34 +4,102 @@
 //! meant as a utility crate for Geo-AID.
 
 use std::{
-    io::{self, Seek, Write},
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+    io::{self, BufWriter, Read, Seek, Write},
     marker::PhantomData,
     ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign},
     rc::Rc,
 };
 
 use num_traits::{Bounded, Num, One, Zero};
+use quick_xml::se::Serializer;
 use raw::{
-    Construction, ConstructionItem, Coords, Element, ElementType, LabelMode, ObjColorType, Show,
+    Axis, Construction, ConstructionItem, CoordSystem, Coords, Element, ElementType, EuclidianView,
+    EvSettings, Font, Grid, Kernel, NonFiniteCoords, ObjColorType, ScreenLocation, Size,
+    StartPoint, ViewNumber,
 };
-use zip::{write::FileOptions, ZipWriter};
+use serde::Serialize;
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
 
 pub mod raw;
-pub use raw::{LineStyle, LineType};
+pub use raw::{GridType, LabelMode, LineStyle, LineType, Show};
+pub use zip::CompressionMethod;
 
 pub mod prelude {
     pub use super::{
-        Conic, ConicAccess, Expr as _, Geogebra, Line, LineAccess, List, ListAccess, Numeric,
-        NumericAccess, Point, PointAccess, Ray, Segment,
+        Angle, Conic, ConicAccess, Curve, Expr as _, Function, Geogebra, ImplicitCurve, Inequality,
+        Line, LineAccess, List, ListAccess, Locus, Numeric, NumericAccess, Point, PointAccess,
+        Polygon, PolygonAccess, Ray, Segment, SegmentAccess, Text, Triangle, TriangleAccess,
+        Vector,
     };
 }
 
+/// Prefix of automatically generated labels, unless set otherwise.
+const DEFAULT_LABEL_PREFIX: &str = "elem";
+
 /// High-level API for working with a Geogebra workspace.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Geogebra {
     data: raw::Geogebra,
+    /// Prefix of automatically generated labels
+    label_prefix: String,
     /// Next element id to use for a label
     next_id: usize,
+    /// PNG thumbnail to include in the file
+    thumbnail: Option<Vec<u8>>,
+    /// Labels of the construction, or `None` if they have to be collected again
+    labels: Option<Labels>,
+}
+
+/// Labels defined in a construction, kept up to date as objects are added.
+#[derive(Debug, Clone, Default)]
+struct Labels {
+    /// Every label defined in the construction
+    defined: HashSet<String>,
+    /// Labels of dependent expressions, keyed by their deduplicated text
+    expressions: HashMap<String, String>,
+}
+
+impl Labels {
+    /// Collect the labels of a construction.
+    fn of(construction: &Construction) -> Self {
+        let mut labels = Self::default();
+
+        for item in &construction.items {
+            match item {
+                ConstructionItem::Expression(expression) => {
+                    labels.defined.insert(expression.label.clone());
+                }
+                ConstructionItem::Command(command) => {
+                    labels.defined.extend(command.output.attrs.iter().cloned());
+                }
+                ConstructionItem::Element(element) => {
+                    labels.defined.insert(element.label.clone());
+                }
+            }
+        }
+
+        for item in &construction.items {
+            if let ConstructionItem::Expression(expression) = item {
+                if labels.is_dependent(&expression.exp) {
+                    labels
+                        .expressions
+                        .insert(expression.exp.clone(), expression.label.clone());
+                }
+            }
+        }
+
+        labels
+    }
+
+    /// Check whether an expression references any object of the construction.
+    fn is_dependent(&self, exp: &str) -> bool {
+        referenced_labels(exp, |label| self.defined.contains(label))
+            .into_iter()
+            .any(|label| self.defined.contains(label))
+    }
 }
 
 impl Geogebra {
@@ -44,306 +112,4035 @@ impl Geogebra {
                 construction: Construction::default(),
                 app: String::from("suite"),
                 sub_app: String::from("geometry"),
+                euclidian_view: Vec::new(),
+                kernel: None,
             },
+            label_prefix: String::from(DEFAULT_LABEL_PREFIX),
             next_id: 0,
+            thumbnail: None,
+            labels: Some(Labels::default()),
         }
     }
 
-    /// Write the ggb file to a stream.
-    pub fn write(&self, stream: impl Write + Seek) -> io::Result<()> {
-        let geogebra = quick_xml::se::to_string(&self.data).unwrap();
+    /// Read a ggb file from a stream. Files from any format version are accepted.
+    ///
+    /// # Errors
+    /// Returns an error if the stream isn't a ggb file or its XML can't be understood.
+    pub fn read(stream: impl Read + Seek) -> io::Result<Self> {
+        let mut file = ZipArchive::new(stream)?;
 
-        let mut file = ZipWriter::new(stream);
+        let mut xml = String::new();
+        file.by_name("geogebra.xml")?.read_to_string(&mut xml)?;
+        let data = quick_xml::de::from_str(&xml)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
-        file.start_file("geogebra.xml", FileOptions::<()>::default())?;
-        file.write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\" ?>")?;
-        file.write_all(geogebra.as_bytes())?;
-        file.finish()?;
+        let mut ggb = Self::from_raw(data);
 
-        Ok(())
+        if let Ok(mut thumbnail) = file.by_name("geogebra_thumbnail.png") {
+            let mut png_bytes = Vec::new();
+            thumbnail.read_to_end(&mut png_bytes)?;
+            ggb.thumbnail = Some(png_bytes);
+        }
+
+        Ok(ggb)
     }
 
-    fn next_label(&mut self) -> String {
-        let mut next_label = format!("elem{}", self.next_id);
-        self.next_id += 1;
+    /// Format version of the file, like `5.0`.
+    #[must_use]
+    pub fn format(&self) -> &str {
+        &self.data.format
+    }
 
-        while self.data.construction.items.iter().any(|item| match item {
-            ConstructionItem::Element(element) => element.label == next_label,
-            ConstructionItem::Command(_) => false,
-            ConstructionItem::Expression(expression) => expression.label == next_label,
-        }) {
-            next_label = format!("elem{}", self.next_id);
-            self.next_id += 1;
+    /// Wrap an existing file model. Automatic labels continue after the ones already used.
+    #[must_use]
+    pub fn from_raw(data: raw::Geogebra) -> Self {
+        let mut next_id = 0;
+
+        for item in &data.construction.items {
+            let defined = match item {
+                ConstructionItem::Expression(expression) => vec![expression.label.as_str()],
+                ConstructionItem::Command(command) => {
+                    command.output.attrs.iter().map(String::as_str).collect()
+                }
+                ConstructionItem::Element(element) => vec![element.label.as_str()],
+            };
+
+            for label in defined {
+                if let Some(id) = label
+                    .strip_prefix(DEFAULT_LABEL_PREFIX)
+                    .and_then(|id| id.parse::<usize>().ok())
+                {
+                    next_id = next_id.max(id + 1);
+                }
+            }
         }
 
-        next_label
+        Self {
+            data,
+            label_prefix: String::from(DEFAULT_LABEL_PREFIX),
+            next_id,
+            thumbnail: None,
+            labels: None,
+        }
     }
-}
 
-#[derive(Clone, Copy)]
-struct Style {
-    /// Whether to display the point's label
-    pub display_label: bool,
-    /// Settings for line display.
-    pub line_style: Option<LineStyle>,
-    /// Color of this object
-    pub color: Option<ObjColorType>,
-}
+    /// Number of construction items. An added object usually takes two:
+    /// its defining expression and its element.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.construction.items.len()
+    }
 
-impl Default for Style {
-    fn default() -> Self {
-        Self {
-            display_label: true,
-            line_style: None,
-            color: None,
-        }
+    /// Check whether the construction has no items.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.construction.items.is_empty()
     }
-}
 
-impl Style {
+    /// Bounding box `(x_min, y_min, x_max, y_max)` of all objects with coordinate hints.
+    /// Returns `None` if no object has one.
     #[must_use]
-    fn to_element(self) -> Element {
-        Element {
-            type_: ElementType::Point,
-            label: String::new(),
-            caption: None,
-            label_mode: LabelMode::Caption.into(),
-            show: Show {
-                object: true,
-                label: self.display_label,
-            },
-            coords: None,
-            line_style: self.line_style,
-            obj_color: self.color,
+    pub fn bounding_box(&self) -> Option<(f64, f64, f64, f64)> {
+        self.data
+            .construction
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ConstructionItem::Element(element) => element.coords,
+                _ => None,
+            })
+            .map(|coords| (coords.x() / coords.z(), coords.y() / coords.z()))
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .fold(None, |bounds, (x, y)| {
+                let (x_min, y_min, x_max, y_max) = bounds.unwrap_or((x, y, x, y));
+                Some((x_min.min(x), y_min.min(y), x_max.max(x), y_max.max(y)))
+            })
+    }
+
+    /// Make the graphics view show the given window. If the view has no size set,
+    /// it's sized to 800x600 pixels.
+    pub fn set_view(&mut self, x_min: f64, y_min: f64, x_max: f64, y_max: f64) {
+        let view = self.view_mut();
+        let size = *view.size.get_or_insert(Size {
+            width: 800,
+            height: 600,
+        });
+
+        let width = if x_max > x_min { x_max - x_min } else { 1.0 };
+        let height = if y_max > y_min { y_max - y_min } else { 1.0 };
+        let scale = f64::from(size.width) / width;
+        let yscale = f64::from(size.height) / height;
+
+        view.coord_system = Some(CoordSystem {
+            x_zero: -x_min * scale,
+            y_zero: y_max * yscale,
+            scale,
+            yscale,
+        });
+    }
+
+    /// Set how many pixels make up one unit along each axis.
+    /// The origin stays where it was, or in the middle of the view if it wasn't set.
+    pub fn set_scale(&mut self, x_scale: f64, y_scale: f64) {
+        let view = self.view_mut();
+        let size = *view.size.get_or_insert(Size {
+            width: 800,
+            height: 600,
+        });
+
+        let coord_system = view.coord_system.get_or_insert(CoordSystem {
+            x_zero: f64::from(size.width) / 2.0,
+            y_zero: f64::from(size.height) / 2.0,
+            scale: 1.0,
+            yscale: 1.0,
+        });
+
+        coord_system.scale = x_scale;
+        coord_system.yscale = y_scale;
+    }
+
+    /// Make the graphics view show all objects with coordinate hints, with a margin around them.
+    /// Does nothing if no object has a hint.
+    pub fn fit_view(&mut self, margin: f64) {
+        if let Some((x_min, y_min, x_max, y_max)) = self.bounding_box() {
+            self.set_view(
+                x_min - margin,
+                y_min - margin,
+                x_max + margin,
+                y_max + margin,
+            );
         }
     }
-}
 
-pub trait Object: Into<Expression> {}
+    /// Set the background color of the graphics view.
+    pub fn set_background_color(&mut self, r: u8, g: u8, b: u8) {
+        self.view_mut().bg_color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
+    }
 
-/// An immutable labeled expression. Passed by reference
-pub struct Var<T>(Rc<String>, PhantomData<T>);
+    /// Set the labels of the X and Y axes.
+    pub fn set_axis_labels(&mut self, x: &str, y: &str) {
+        self.axis_mut(0).label = Some(x.to_string());
+        self.axis_mut(1).label = Some(y.to_string());
+    }
 
-impl<T> Var<T> {
+    /// Set the units of the X and Y axes. `None` removes the unit.
+    pub fn set_axis_unit(&mut self, x: Option<&str>, y: Option<&str>) {
+        self.axis_mut(0).unit_label = x.map(ToString::to_string);
+        self.axis_mut(1).unit_label = y.map(ToString::to_string);
+    }
+
+    /// Show the grid of the given type. Without spacing, Geogebra picks it automatically.
+    pub fn set_grid(&mut self, type_: GridType, spacing: Option<(f64, f64)>) {
+        let view = self.view_mut();
+        let settings = view.ev_settings.get_or_insert_with(EvSettings::default);
+        settings.grid = Some(true);
+        settings.grid_type = Some(type_);
+        view.grid = spacing.map(|(dist_x, dist_y)| Grid { dist_x, dist_y });
+    }
+
+    /// Get the settings of an axis, creating them if necessary.
+    fn axis_mut(&mut self, id: u8) -> &mut Axis {
+        let axes = &mut self.view_mut().axis;
+
+        if let Some(i) = axes.iter().position(|axis| axis.id == id) {
+            &mut axes[i]
+        } else {
+            axes.push(Axis {
+                id,
+                ..Axis::default()
+            });
+            axes.last_mut().unwrap()
+        }
+    }
+
+    /// Add the second graphics view. Does nothing if it already exists.
+    pub fn add_second_view(&mut self) {
+        self.numbered_view_mut(2);
+    }
+
+    /// Choose which graphics views an object appears in.
+    pub fn set_graphics_views<T>(&mut self, var: &Var<T>, first: bool, second: bool) {
+        if second {
+            self.add_second_view();
+        }
+
+        if let Some(element) = self.element_mut(&var.0) {
+            let ev = u8::from(!first) | (u8::from(second) << 1);
+            element.show.ev = (ev != 0).then_some(ev);
+        }
+    }
+
+    /// Get the first graphics view settings, creating them if necessary.
+    fn view_mut(&mut self) -> &mut EuclidianView {
+        self.numbered_view_mut(1)
+    }
+
+    /// Get the settings of a graphics view, creating them if necessary.
+    fn numbered_view_mut(&mut self, number: u8) -> &mut EuclidianView {
+        let views = &mut self.data.euclidian_view;
+        let view_no = |view: &EuclidianView| view.view_number.map_or(1, |v| v.view_no);
+
+        if let Some(i) = views.iter().position(|view| view_no(view) == number) {
+            &mut views[i]
+        } else {
+            views.push(EuclidianView {
+                view_number: (number != 1).then_some(ViewNumber { view_no: number }),
+                ..EuclidianView::default()
+            });
+            views.last_mut().unwrap()
+        }
+    }
+
+    /// Set the prefix of automatically generated labels. Numbering restarts from 0,
+    /// skipping labels that are already taken.
+    ///
+    /// # Errors
+    /// Returns an error if labels with this prefix would be malformed.
+    /// Reserved words are allowed, since a number always follows them.
+    pub fn set_label_prefix(&mut self, prefix: &str) -> Result<(), LabelError> {
+        match validate_label(prefix) {
+            Ok(())
+            | Err(LabelError::Invalid {
+                rule: LabelRule::Reserved,
+                ..
+            }) => (),
+            Err(err) => return Err(err),
+        }
+
+        self.label_prefix = prefix.to_string();
+        self.next_id = 0;
+        Ok(())
+    }
+
+    /// The underlying file model.
     #[must_use]
-    fn new(expr: String) -> Self {
-        Self(Rc::new(expr), PhantomData)
+    pub fn raw(&self) -> &raw::Geogebra {
+        &self.data
     }
-}
 
-impl<T: Object> Object for Var<T> {}
+    /// The underlying file model, for changes the typed API doesn't cover.
+    /// Labels are collected again before the next object is added.
+    pub fn raw_mut(&mut self) -> &mut raw::Geogebra {
+        self.labels = None;
+        &mut self.data
+    }
 
-impl<T: Object> Object for &Var<T> {}
+    /// Round displayed values to a number of decimal places.
+    pub fn set_rounding(&mut self, decimals: u8) {
+        let kernel = self.data.kernel.get_or_insert_with(Kernel::default);
+        kernel.decimals = Some(decimals.into());
+        kernel.significant_figures = None;
+    }
 
-impl<T: Expr> Expr for Var<T> {
-    type Target = T::Target;
+    /// Round displayed values to a number of significant figures instead of decimal places.
+    pub fn set_significant_figures(&mut self, figs: u8) {
+        let kernel = self.data.kernel.get_or_insert_with(Kernel::default);
+        kernel.significant_figures = Some(figs.into());
+        kernel.decimals = None;
+    }
 
-    fn get_type() -> ElementType {
-        T::get_type()
+    /// Set the title of the construction.
+    pub fn set_title(&mut self, title: &str) {
+        self.data.construction.title = Some(title.to_string());
     }
 
-    fn var(expr: String) -> Var<Self::Target> {
-        T::var(expr)
+    /// Set the author of the construction.
+    pub fn set_author(&mut self, author: &str) {
+        self.data.construction.author = Some(author.to_string());
     }
-}
 
-impl<T: Expr> Expr for &Var<T> {
-    type Target = T::Target;
+    /// Set the date of the construction. Any format is accepted.
+    pub fn set_date(&mut self, date: &str) {
+        self.data.construction.date = Some(date.to_string());
+    }
 
-    fn get_type() -> ElementType {
-        T::get_type()
+    /// Set the PNG thumbnail included in the written file.
+    pub fn set_thumbnail(&mut self, png_bytes: Vec<u8>) {
+        self.thumbnail = Some(png_bytes);
     }
 
-    fn var(expr: String) -> Var<Self::Target> {
-        T::var(expr)
+    /// Write the ggb file to a stream.
+    pub fn write(&self, stream: impl Write + Seek) -> io::Result<()> {
+        self.write_with_options(stream, WriteOptions::default())
     }
-}
 
-/// A type-erased expression.
-#[derive(Clone)]
-pub struct Expression {
-    expr: Rc<String>,
-    style: Style,
-}
+    /// Write the ggb file to a stream, with the XML indented.
+    pub fn write_pretty(&self, stream: impl Write + Seek) -> io::Result<()> {
+        self.write_with_options(stream, WriteOptions::default().pretty(true))
+    }
 
-impl Expression {
-    /// Expression from a string, default style.
-    pub fn expr(expr: impl ToString) -> Self {
-        Self {
-            expr: Rc::new(expr.to_string()),
-            style: Style::default(),
+    /// Write the ggb file to a stream with the given zip options.
+    pub fn write_with_options(
+        &self,
+        stream: impl Write + Seek,
+        options: WriteOptions,
+    ) -> io::Result<()> {
+        let mut file = ZipWriter::new(stream);
+
+        file.start_file("geogebra.xml", options.file)?;
+        file.write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\" ?>")?;
+
+        if options.pretty {
+            file.write_all(b"\n")?;
+        }
+
+        self.serialize_into(&mut file, options.pretty)?;
+
+        if let Some(thumbnail) = &self.thumbnail {
+            file.start_file("geogebra_thumbnail.png", options.file)?;
+            file.write_all(thumbnail)?;
         }
+
+        file.finish()?;
+
+        Ok(())
     }
-}
 
-pub trait Expr: Into<Expression> {
-    /// Target primitive type.
-    type Target;
+    /// Serialize the XML straight into a sink, without building it in memory first.
+    fn serialize_into(&self, sink: impl Write, pretty: bool) -> io::Result<()> {
+        let mut writer = IoWriter {
+            inner: BufWriter::new(sink),
+            error: None,
+        };
 
-    /// Get the element type of this expression
-    fn get_type() -> ElementType;
+        let mut serializer = Serializer::new(&mut writer);
 
-    /// Create a variable of target type
-    #[must_use]
-    fn var(expr: String) -> Var<Self::Target>;
-}
+        if pretty {
+            serializer.indent(' ', 2);
+        }
 
-impl<X: Into<Numeric>, Y: Into<Numeric>> From<(X, Y)> for Expression {
-    fn from((x, y): (X, Y)) -> Self {
-        Self {
-            expr: Rc::new(format!(
-                "(real({}), real({}))",
-                x.into().0.expr,
-                y.into().0.expr
-            )),
-            style: Style::default(),
+        let result = self.data.serialize(serializer);
+
+        if let Some(err) = writer.error {
+            return Err(err);
+        }
+
+        result.map_err(io::Error::other)?;
+        writer.inner.flush()
+    }
+
+    /// Write one ggb file per value, each with the number pinned to that value.
+    ///
+    /// # Errors
+    /// Returns an error if the number isn't defined by an expression in this construction,
+    /// if a value is not finite, or if writing a file fails.
+    pub fn animate(&self, var: &Var<Numeric>, values: &[f64]) -> io::Result<Vec<Vec<u8>>> {
+        let is_defined = self.data.construction.items.iter().any(|item| {
+            matches!(item, ConstructionItem::Expression(expression) if expression.label == *var.0)
+        });
+
+        if !is_defined {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("no expression is labeled `{}`", var.0),
+            ));
+        }
+
+        if let Some(value) = values.iter().find(|value| !value.is_finite()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("animated value {value} is not finite"),
+            ));
+        }
+
+        let mut frame = self.clone();
+
+        values
+            .iter()
+            .map(|&value| {
+                for item in &mut frame.data.construction.items {
+                    match item {
+                        ConstructionItem::Expression(expression) if expression.label == *var.0 => {
+                            expression.exp = value.to_string();
+                        }
+                        // Sliders also store their value in the element.
+                        ConstructionItem::Element(element)
+                            if element.label == *var.0 && element.value.is_some() =>
+                        {
+                            element.value = Some(value.into());
+                        }
+                        _ => (),
+                    }
+                }
+
+                let mut file = io::Cursor::new(Vec::new());
+                frame.write(&mut file)?;
+                Ok(file.into_inner())
+            })
+            .collect()
+    }
+
+    /// Check that every object only references labels defined before it
+    /// and that no label is defined twice.
+    ///
+    /// # Errors
+    /// Returns all problems found.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let items = &self.data.construction.items;
+        let mut defined = HashMap::new();
+        let mut errors = Vec::new();
+
+        for (i, item) in items.iter().enumerate() {
+            let labels = match item {
+                ConstructionItem::Expression(expression) => vec![expression.label.as_str()],
+                ConstructionItem::Command(command) => {
+                    command.output.attrs.iter().map(String::as_str).collect()
+                }
+                ConstructionItem::Element(_) => Vec::new(),
+            };
+
+            for label in labels {
+                if defined.insert(label, i).is_some() {
+                    errors.push(ValidationError::DuplicateLabel(label.to_string()));
+                }
+            }
+        }
+
+        // Elements without a defining expression are free objects.
+        for (i, item) in items.iter().enumerate() {
+            if let ConstructionItem::Element(element) = item {
+                defined.entry(element.label.as_str()).or_insert(i);
+            }
+        }
+
+        for (i, item) in items.iter().enumerate() {
+            let (label, references) = match item {
+                ConstructionItem::Expression(expression) => (
+                    expression.label.as_str(),
+                    // `f(x) = ...` calls itself only to name its parameters.
+                    referenced_labels(&expression.exp, |label| {
+                        label != expression.label && defined.contains_key(label)
+                    }),
+                ),
+                ConstructionItem::Command(command) => (
+                    command.output.attrs.first().map_or("", String::as_str),
+                    command
+                        .input
+                        .attrs
+                        .iter()
+                        .flat_map(|input| {
+                            referenced_labels(input, |label| defined.contains_key(label))
+                        })
+                        .collect(),
+                ),
+                ConstructionItem::Element(_) => continue,
+            };
+
+            for reference in references {
+                match defined.get(reference) {
+                    None => errors.push(ValidationError::DanglingReference {
+                        label: label.to_string(),
+                        reference: reference.to_string(),
+                    }),
+                    Some(&j) if j >= i => errors.push(ValidationError::UsedBeforeDefinition {
+                        label: label.to_string(),
+                        reference: reference.to_string(),
+                    }),
+                    Some(_) => (),
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
     }
-}
 
-impl From<f64> for Expression {
-    fn from(value: f64) -> Self {
-        Self {
-            expr: Rc::new(format!("{value} + 0i")),
-            style: Style::default(),
+    /// Reorder the construction so that every object is defined before it's referenced.
+    /// Already well-ordered parts keep their relative order.
+    ///
+    /// # Errors
+    /// Returns an error if the objects depend on each other cyclically.
+    pub fn sort_dependencies(&mut self) -> Result<(), CycleError> {
+        let items = &self.data.construction.items;
+        // Items are grouped by the object they define.
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut group_labels = Vec::new();
+        let mut group_of = HashMap::new();
+
+        for (i, item) in items.iter().enumerate() {
+            let labels = match item {
+                ConstructionItem::Expression(expression) => vec![expression.label.as_str()],
+                ConstructionItem::Command(command) => {
+                    command.output.attrs.iter().map(String::as_str).collect()
+                }
+                ConstructionItem::Element(element) => vec![element.label.as_str()],
+            };
+
+            let group = labels
+                .iter()
+                .find_map(|label| group_of.get(label).copied())
+                .unwrap_or_else(|| {
+                    groups.push(Vec::new());
+                    group_labels.push(labels.first().copied().unwrap_or_default());
+                    groups.len() - 1
+                });
+
+            groups[group].push(i);
+
+            for label in labels {
+                group_of.entry(label).or_insert(group);
+            }
+        }
+
+        let dependencies: Vec<Vec<usize>> = groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .flat_map(|&i| match &items[i] {
+                        ConstructionItem::Expression(expression) => {
+                            referenced_labels(&expression.exp, |label| {
+                                label != expression.label && group_of.contains_key(label)
+                            })
+                        }
+                        ConstructionItem::Command(command) => command
+                            .input
+                            .attrs
+                            .iter()
+                            .flat_map(|input| {
+                                referenced_labels(input, |label| group_of.contains_key(label))
+                            })
+                            .collect(),
+                        // Texts can be anchored to a point.
+                        ConstructionItem::Element(element) => element
+                            .start_point
+                            .as_ref()
+                            .and_then(|start_point| start_point.exp.as_deref())
+                            .map(|exp| referenced_labels(exp, |label| group_of.contains_key(label)))
+                            .unwrap_or_default(),
+                    })
+                    .filter_map(|label| group_of.get(label).copied())
+                    .collect()
+            })
+            .collect();
+
+        // Iterative depth-first search, visiting groups in their original order.
+        let mut visited = vec![false; groups.len()];
+        let mut finished = vec![false; groups.len()];
+        let mut order = Vec::with_capacity(groups.len());
+
+        for root in 0..groups.len() {
+            if visited[root] {
+                continue;
+            }
+
+            visited[root] = true;
+            let mut stack = vec![(root, 0)];
+
+            while let Some((group, next)) = stack.last_mut() {
+                let group = *group;
+
+                if let Some(&dependency) = dependencies[group].get(*next) {
+                    *next += 1;
+
+                    if !visited[dependency] {
+                        visited[dependency] = true;
+                        stack.push((dependency, 0));
+                    } else if !finished[dependency] {
+                        let start = stack.iter().position(|&(g, _)| g == dependency);
+                        return Err(CycleError {
+                            labels: stack[start.unwrap_or_default()..]
+                                .iter()
+                                .map(|&(g, _)| group_labels[g].to_string())
+                                .chain([group_labels[dependency].to_string()])
+                                .collect(),
+                        });
+                    }
+                } else {
+                    finished[group] = true;
+                    order.push(group);
+                    stack.pop();
+                }
+            }
         }
+
+        let mut items: Vec<_> = std::mem::take(&mut self.data.construction.items)
+            .into_iter()
+            .map(Some)
+            .collect();
+
+        let mut sorted = Vec::with_capacity(items.len());
+
+        for group in order {
+            for &i in &groups[group] {
+                sorted.extend(items[i].take());
+            }
+        }
+
+        self.data.construction.items = sorted;
+
+        Ok(())
     }
-}
 
-impl<T> From<Var<T>> for Expression {
-    fn from(value: Var<T>) -> Self {
-        Self::from(&value)
+    /// Write the construction as GeoGebra input bar commands, one per line.
+    /// Pasting them into GeoGebra recreates the objects, but not their styling.
+    #[must_use]
+    pub fn to_commands(&self) -> String {
+        let items = &self.data.construction.items;
+        let mut defined = HashSet::new();
+
+        for item in items {
+            match item {
+                ConstructionItem::Expression(expression) => {
+                    defined.insert(expression.label.as_str());
+                }
+                ConstructionItem::Command(command) => {
+                    defined.extend(command.output.attrs.iter().map(String::as_str));
+                }
+                ConstructionItem::Element(_) => (),
+            }
+        }
+
+        let mut commands = String::new();
+
+        for item in items {
+            match item {
+                ConstructionItem::Expression(expression) => {
+                    commands.push_str(&format!("{} = {}\n", expression.label, expression.exp));
+                }
+                ConstructionItem::Command(command) => {
+                    commands.push_str(&format!(
+                        "{} = {}({})\n",
+                        command.output.attrs.join(", "),
+                        command.name,
+                        command.input.attrs.join(", ")
+                    ));
+                }
+                // Elements without a defining expression are free objects.
+                ConstructionItem::Element(element) => {
+                    if defined.contains(element.label.as_str()) {
+                        continue;
+                    }
+
+                    if let Some(coords) = &element.coords {
+                        commands.push_str(&format!(
+                            "{} = ({}, {})\n",
+                            element.label,
+                            coords.x() / coords.z(),
+                            coords.y() / coords.z()
+                        ));
+                    }
+                }
+            }
+        }
+
+        commands
     }
-}
 
-impl<T> From<&Var<T>> for Expression {
-    fn from(value: &Var<T>) -> Self {
-        Self {
-            expr: Rc::clone(&value.0),
-            style: Style::default(),
+    /// Labels of the construction, collected again if it was changed through [`Geogebra::raw_mut`].
+    fn labels(&mut self) -> &mut Labels {
+        self.labels
+            .get_or_insert_with(|| Labels::of(&self.data.construction))
+    }
+
+    /// Check whether a label is already used in the construction.
+    fn is_label_taken(&mut self, label: &str) -> bool {
+        self.labels().defined.contains(label)
+    }
+
+    /// Find the element with the given label.
+    fn element_mut(&mut self, label: &str) -> Option<&mut Element> {
+        self.data
+            .construction
+            .items
+            .iter_mut()
+            .find_map(|item| match item {
+                ConstructionItem::Element(element) if element.label == label => {
+                    Some(&mut **element)
+                }
+                _ => None,
+            })
+    }
+
+    fn next_label(&mut self) -> String {
+        let mut next_label = format!("{}{}", self.label_prefix, self.next_id);
+        self.next_id += 1;
+
+        while self.is_label_taken(&next_label) {
+            next_label = format!("{}{}", self.label_prefix, self.next_id);
+            self.next_id += 1;
         }
+
+        next_label
     }
 }
 
-impl From<Point> for Expression {
-    fn from(value: Point) -> Self {
-        value.0
+/// Replace subexpressions that already have a label with that label.
+/// Labels are keyed by their deduplicated expressions, so inner subexpressions
+/// are replaced first. The expression as a whole is never replaced and string
+/// literals are left untouched.
+fn dedup_expression(exp: &str, labels: &HashMap<String, String>) -> String {
+    if labels.is_empty() {
+        return exp.to_string();
     }
+
+    let closing = matching_parens(exp);
+    dedup_span(exp, 0..exp.len(), &closing, labels)
 }
 
-/// A point element of the Geogebra construction
-#[derive(Clone)]
-pub struct Point(Expression);
+/// Deduplicate the part of `exp` in `span`. See [`dedup_expression`].
+fn dedup_span(
+    exp: &str,
+    span: std::ops::Range<usize>,
+    closing: &HashMap<usize, usize>,
+    labels: &HashMap<String, String>,
+) -> String {
+    let mut result = String::with_capacity(span.len());
+    let mut ident_start = None;
+    let mut pos = span.start;
+
+    while let Some(c) = exp[pos..span.end].chars().next() {
+        let next = pos + c.len_utf8();
+
+        if c.is_alphanumeric() || c == '_' {
+            ident_start.get_or_insert(pos);
+            pos = next;
+            continue;
+        }
 
-impl Point {
-    /// Set the line's color
-    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
-        self.0.style.color = Some(ObjColorType { r, g, b });
+        let start = ident_start.take().unwrap_or(pos);
+
+        if c == '"' {
+            let end = exp[next..span.end]
+                .find('"')
+                .map_or(span.end, |i| next + i + 1);
+            result += &exp[start..end];
+            pos = end;
+            continue;
+        }
+
+        if let Some(&end) = closing.get(&pos) {
+            let inner = dedup_span(exp, next..end, closing, labels);
+            let call = format!("{}({inner})", &exp[start..pos]);
+
+            match labels.get(&call) {
+                Some(label) if end - start + 1 != exp.len() => result += label,
+                _ => result += &call,
+            }
+
+            pos = end + 1;
+            continue;
+        }
+
+        result += &exp[start..next];
+        pos = next;
     }
 
-    /// Wether to display this line's label
-    pub fn set_display_label(&mut self, v: bool) {
-        self.0.style.display_label = v;
+    if let Some(start) = ident_start {
+        result += &exp[start..span.end];
     }
 
-    /// Style for a point bound to its expression
-    #[must_use]
-    fn bound() -> Style {
-        Style {
+    result
+}
+
+/// Map positions of opening parentheses to positions of their closing counterparts.
+/// Parentheses inside string literals are skipped.
+fn matching_parens(exp: &str) -> HashMap<usize, usize> {
+    let mut closing = HashMap::new();
+    let mut open = Vec::new();
+    let mut in_string = false;
+
+    for (i, c) in exp.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            _ if in_string => (),
+            '(' => open.push(i),
+            ')' => {
+                if let Some(start) = open.pop() {
+                    closing.insert(start, i);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    closing
+}
+
+/// Local index variable of generated `Sequence` and `Sum` calls.
+/// It can't be used as a label, so it never shadows one.
+const INDEX_VARIABLE: &str = "idx";
+
+/// Local variable generated `Zip` calls bind a list to, so that it's only written once.
+/// It can't be used as a label either.
+const LIST_VARIABLE: &str = "lst";
+
+/// Words that cannot be used as labels.
+const RESERVED_LABELS: &[&str] = &[
+    "x", "y", "z", "e", "i", "pi", "true", "false", "xAxis", "yAxis", "sin", "cos", "tan", "ln",
+    "exp", "sqrt", "abs",
+];
+
+/// Check whether a label matches Geogebra's identifier grammar.
+fn validate_label(label: &str) -> Result<(), LabelError> {
+    let invalid = |rule| {
+        Err(LabelError::Invalid {
+            label: label.to_string(),
+            rule,
+        })
+    };
+
+    let Some(first) = label.chars().next() else {
+        return invalid(LabelRule::Empty);
+    };
+
+    if first.is_numeric() {
+        return invalid(LabelRule::LeadingDigit);
+    }
+
+    if let Some(c) = label
+        .chars()
+        .find(|c| !c.is_alphanumeric() && !matches!(c, '_' | '\'' | '{' | '}'))
+    {
+        return invalid(LabelRule::InvalidCharacter(c));
+    }
+
+    if RESERVED_LABELS.contains(&label) || [INDEX_VARIABLE, LIST_VARIABLE].contains(&label) {
+        return invalid(LabelRule::Reserved);
+    }
+
+    Ok(())
+}
+
+/// A rule of the label grammar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelRule {
+    /// Labels cannot be empty
+    Empty,
+    /// Labels cannot start with a digit
+    LeadingDigit,
+    /// Labels can only contain letters, digits, `_`, `'`, `{` and `}`
+    InvalidCharacter(char),
+    /// Labels cannot be reserved words
+    Reserved,
+}
+
+impl fmt::Display for LabelRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "labels cannot be empty"),
+            Self::LeadingDigit => write!(f, "labels cannot start with a digit"),
+            Self::InvalidCharacter(c) => write!(f, "labels cannot contain `{c}`"),
+            Self::Reserved => write!(f, "labels cannot be reserved words"),
+        }
+    }
+}
+
+/// Error of adding a named object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelError {
+    /// The label is malformed
+    Invalid {
+        /// The offending label
+        label: String,
+        /// The violated rule
+        rule: LabelRule,
+    },
+    /// The label is already used in the construction
+    Taken(String),
+}
+
+impl fmt::Display for LabelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid { label, rule } => write!(f, "invalid label `{label}`: {rule}"),
+            Self::Taken(label) => write!(f, "label `{label}` is already taken"),
+        }
+    }
+}
+
+impl std::error::Error for LabelError {}
+
+/// Commands that bind a local variable as their second argument.
+const BINDING_COMMANDS: &[&str] = &["Sum", "Product", "Sequence", "Zip"];
+
+/// Extract labels referenced by an expression. Command names, reserved words,
+/// number literals, strings and locally bound variables are skipped.
+/// A name followed by `(` is a call, and it's only a reference when `is_defined`
+/// says it's a label, like a user function.
+fn referenced_labels(exp: &str, is_defined: impl Fn(&str) -> bool) -> Vec<&str> {
+    let closing = matching_parens(exp);
+    let mut idents = Vec::new();
+    let mut bound = Vec::new();
+    let mut chars = exp.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c == '"' {
+            for (_, c) in chars.by_ref() {
+                if c == '"' {
+                    break;
+                }
+            }
+        } else if c.is_ascii_digit() || c == '.' {
+            while chars
+                .next_if(|(_, c)| c.is_alphanumeric() || *c == '.')
+                .is_some()
+            {}
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = exp.len();
+
+            while let Some(&(i, c)) = chars.peek() {
+                if c == '{' && exp[..i].ends_with('_') {
+                    while chars.next_if(|(_, c)| *c != '}').is_some() {}
+                    chars.next();
+                } else if c.is_alphanumeric() || c == '_' || c == '\'' {
+                    chars.next();
+                } else {
+                    end = i;
+                    break;
+                }
+            }
+
+            let ident = &exp[start..end];
+            let rest = exp[end..].trim_start();
+
+            if rest.starts_with('(') {
+                let open = exp.len() - rest.len();
+
+                if is_defined(ident) {
+                    idents.push(ident);
+                }
+
+                if BINDING_COMMANDS.contains(&ident) {
+                    if let Some(&close) = closing.get(&open) {
+                        let args = split_arguments(&exp[open + 1..close]);
+
+                        if args.len() >= 3 {
+                            bound.push(args[1].trim());
+                        }
+                    }
+                }
+            } else if !RESERVED_LABELS.contains(&ident) {
+                idents.push(ident);
+            }
+        }
+    }
+
+    idents.retain(|ident| !bound.contains(ident));
+    idents
+}
+
+/// Split a comma-separated argument list on its top level.
+fn split_arguments(args: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = 0;
+
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' | '{' | '[' => depth += 1,
+            ')' | '}' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                result.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+
+    result.push(&args[start..]);
+    result
+}
+
+/// A problem found by [`Geogebra::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// An object references a label that is not defined anywhere
+    DanglingReference {
+        /// The referencing object
+        label: String,
+        /// The missing label
+        reference: String,
+    },
+    /// An object references a label that is only defined after it
+    UsedBeforeDefinition {
+        /// The referencing object
+        label: String,
+        /// The label defined too late
+        reference: String,
+    },
+    /// A label is defined more than once
+    DuplicateLabel(String),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DanglingReference { label, reference } => {
+                write!(f, "`{label}` references undefined `{reference}`")
+            }
+            Self::UsedBeforeDefinition { label, reference } => {
+                write!(
+                    f,
+                    "`{label}` references `{reference}` before its definition"
+                )
+            }
+            Self::DuplicateLabel(label) => write!(f, "label `{label}` is defined more than once"),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Error of sorting a construction with cyclic dependencies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError {
+    /// Labels forming the cycle, each depending on the next one
+    pub labels: Vec<String>,
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cyclic dependency: {}", self.labels.join(" -> "))
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+/// Options for writing the ggb file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteOptions {
+    /// Options of the zip entries
+    file: FileOptions<'static, ()>,
+    /// Whether to indent the XML
+    pretty: bool,
+}
+
+impl WriteOptions {
+    /// Set the compression method of the zip entries.
+    #[must_use]
+    pub fn compression_method(mut self, method: CompressionMethod) -> Self {
+        self.file = self.file.compression_method(method);
+        self
+    }
+
+    /// Set the compression level. `None` means the method's default.
+    #[must_use]
+    pub fn compression_level(mut self, level: Option<i64>) -> Self {
+        self.file = self.file.compression_level(level);
+        self
+    }
+
+    /// Indent the XML for easier reading.
+    #[must_use]
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+}
+
+/// Adapter for writing formatted output into an io sink.
+struct IoWriter<W: Write> {
+    inner: W,
+    /// The io error that interrupted writing, if any
+    error: Option<io::Error>,
+}
+
+impl<W: Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Builder for element captions. Can be passed anywhere a caption is expected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Caption(String);
+
+impl Caption {
+    /// Plain text caption
+    #[must_use]
+    pub fn text(text: impl ToString) -> Self {
+        Self(text.to_string())
+    }
+
+    /// Caption rendered as LaTeX
+    #[must_use]
+    pub fn latex(latex: impl ToString) -> Self {
+        Self(format!("${}$", latex.to_string()))
+    }
+
+    /// Caption from a template, where every `{}` is replaced by the object's value
+    #[must_use]
+    pub fn with_value(template: impl ToString) -> Self {
+        Self(template.to_string().replace("{}", "%v"))
+    }
+
+    /// Caption from a template, where every `{}` is replaced by the object's name
+    #[must_use]
+    pub fn with_name(template: impl ToString) -> Self {
+        Self(template.to_string().replace("{}", "%n"))
+    }
+
+    /// Append plain text
+    #[must_use]
+    pub fn push_text(mut self, text: impl ToString) -> Self {
+        self.0 += &text.to_string();
+        self
+    }
+
+    /// Append the object's value
+    #[must_use]
+    pub fn push_value(mut self) -> Self {
+        self.0 += "%v";
+        self
+    }
+
+    /// Append the object's value followed by a percent sign, for use with [`Numeric::percent`]
+    #[must_use]
+    pub fn push_percent(mut self) -> Self {
+        self.0 += "%v%";
+        self
+    }
+
+    /// Append the object's name
+    #[must_use]
+    pub fn push_name(mut self) -> Self {
+        self.0 += "%n";
+        self
+    }
+}
+
+impl fmt::Display for Caption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Clone)]
+struct Style {
+    /// Whether to display the point's label
+    pub display_label: bool,
+    /// Settings for line display.
+    pub line_style: Option<LineStyle>,
+    /// Color of this object
+    pub color: Option<ObjColorType>,
+    /// Font of the label
+    pub font: Option<Font>,
+    /// Expression of the point this object is anchored to
+    pub start_point: Option<Rc<Node>>,
+    /// Whether the object is rendered as LaTeX
+    pub latex: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
             display_label: true,
             line_style: None,
-            color: Some(ObjColorType {
-                r: 97,
-                g: 97,
-                b: 97,
+            color: None,
+            font: None,
+            start_point: None,
+            latex: false,
+        }
+    }
+}
+
+impl Style {
+    /// Set the stroke, keeping the rest of the line style.
+    fn set_line_type(&mut self, type_: LineType) {
+        self.line_style.get_or_insert_with(LineStyle::default).type_ = Some(type_);
+    }
+
+    /// Set the line opacity, clamped to `[0, 1]`. Non-finite values are ignored.
+    fn set_opacity(&mut self, opacity: f64) {
+        if opacity.is_finite() {
+            self.line_style
+                .get_or_insert_with(LineStyle::default)
+                .opacity = Some(opacity.clamp(0.0, 1.0));
+        }
+    }
+
+    /// Set the color with the opacity of the filling, clamped to `[0, 1]`.
+    /// Non-finite opacities are ignored.
+    fn set_color_alpha(&mut self, r: u8, g: u8, b: u8, alpha: f64) {
+        if alpha.is_finite() {
+            self.color = Some(ObjColorType {
+                r,
+                g,
+                b,
+                alpha: Some(alpha.clamp(0.0, 1.0)),
+            });
+        }
+    }
+
+    /// Set the size of the label, relative to the default size.
+    fn set_caption_size(&mut self, size: i8) {
+        self.font.get_or_insert_with(Font::default).size = size;
+    }
+
+    /// Set whether the label is bold and/or italic.
+    fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.font.get_or_insert_with(Font::default).style =
+            u8::from(bold) | (u8::from(italic) << 1);
+    }
+
+    #[must_use]
+    fn to_element(&self) -> Element {
+        Element {
+            type_: ElementType::Point,
+            label: String::new(),
+            caption: None,
+            label_mode: LabelMode::Caption.into(),
+            show: Show {
+                object: true,
+                label: self.display_label,
+                ev: None,
+            },
+            coords: None,
+            line_style: self.line_style,
+            point_size: None,
+            point_style: None,
+            obj_color: self.color,
+            absolute_screen_location: None,
+            font: self.font,
+            start_point: self.start_point.as_ref().map(|exp| StartPoint {
+                exp: Some(exp.to_string()),
+                ..StartPoint::default()
             }),
+            is_latex: self.latex.then_some(true.into()),
+            value: None,
+            slider: None,
+            animation: None,
         }
     }
+}
 
-    /// Style for a point bound to its expression
+pub trait Object: Into<Expression> {}
+
+/// An immutable labeled expression. Passed by reference
+pub struct Var<T>(Rc<String>, PhantomData<T>);
+
+impl<T> Var<T> {
+    #[must_use]
+    fn new(expr: String) -> Self {
+        Self(Rc::new(expr), PhantomData)
+    }
+}
+
+impl<T> Clone for Var<T> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0), PhantomData)
+    }
+}
+
+impl<T: Object> Object for Var<T> {}
+
+impl<T: Object> Object for &Var<T> {}
+
+impl<T: Expr> Expr for Var<T> {
+    type Target = T::Target;
+
+    fn get_type() -> ElementType {
+        T::get_type()
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        T::var(expr)
+    }
+}
+
+impl<T: Expr> Expr for &Var<T> {
+    type Target = T::Target;
+
+    fn get_type() -> ElementType {
+        T::get_type()
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        T::var(expr)
+    }
+}
+
+/// Text of an expression, only put together when it's added to the construction.
+/// Building an expression nests nodes instead of formatting strings,
+/// so subexpressions are never copied.
+enum Node {
+    /// Plain text
+    Text(String),
+    /// Label of a variable, shared with the variable itself
+    Label(Rc<String>),
+    /// `(lhs) op (rhs)`
+    Binary(Rc<Node>, &'static str, Rc<Node>),
+    /// `-(operand)`
+    Neg(Rc<Node>),
+    /// Text with `{0}`, `{1}`, ... replaced by the arguments. `{{` and `}}` are braces.
+    Template(&'static str, Vec<Rc<Node>>),
+    /// `name(args, ...)`
+    Call(&'static str, Vec<Rc<Node>>),
+    /// `{items, ...}`
+    List(Vec<Rc<Node>>),
+}
+
+impl Node {
+    fn text(text: impl ToString) -> Rc<Self> {
+        Rc::new(Self::Text(text.to_string()))
+    }
+
+    fn template<const N: usize>(template: &'static str, args: [Rc<Self>; N]) -> Rc<Self> {
+        Rc::new(Self::Template(template, args.into()))
+    }
+
+    fn call(name: &'static str, args: impl IntoIterator<Item = Rc<Self>>) -> Rc<Self> {
+        Rc::new(Self::Call(name, args.into_iter().collect()))
+    }
+
+    /// Write comma-separated arguments.
+    fn write_args(f: &mut fmt::Formatter<'_>, args: &[Rc<Self>]) -> fmt::Result {
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{arg}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text) => f.write_str(text),
+            Self::Label(label) => f.write_str(label),
+            Self::Binary(lhs, op, rhs) => write!(f, "({lhs}) {op} ({rhs})"),
+            Self::Neg(operand) => write!(f, "-({operand})"),
+            Self::Template(template, args) => {
+                let mut rest = *template;
+
+                while let Some(i) = rest.find(['{', '}']) {
+                    f.write_str(&rest[..i])?;
+                    let brace = &rest[i..=i];
+
+                    if rest[i + 1..].starts_with(brace) {
+                        f.write_str(brace)?;
+                        rest = &rest[i + 2..];
+                        continue;
+                    }
+
+                    let end = i + rest[i..].find('}').expect("unclosed placeholder");
+                    let index: usize = rest[i + 1..end].parse().expect("invalid placeholder");
+                    write!(f, "{}", args[index])?;
+                    rest = &rest[end + 1..];
+                }
+
+                f.write_str(rest)
+            }
+            Self::Call(name, args) => {
+                write!(f, "{name}(")?;
+                Self::write_args(f, args)?;
+                f.write_str(")")
+            }
+            Self::List(items) => {
+                f.write_str("{")?;
+                Self::write_args(f, items)?;
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Nodes are equal when they make the same text.
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+/// A type-erased expression.
+///
+/// Every object type can be made from an expression with `From`. The caller is
+/// responsible for the expression being of the right type.
+#[derive(Clone)]
+pub struct Expression {
+    expr: Rc<Node>,
+    style: Style,
+}
+
+impl Expression {
+    /// Expression from a string, default style.
+    pub fn expr(expr: impl ToString) -> Self {
+        Self::new(Node::text(expr))
+    }
+
+    /// Expression from a node, default style.
+    fn new(expr: Rc<Node>) -> Self {
+        Self {
+            expr,
+            style: Style::default(),
+        }
+    }
+
+    /// Set the color of the object defined by this expression
+    #[must_use]
+    pub fn with_color(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
+        self
+    }
+
+    /// Set the line style of the object defined by this expression
+    #[must_use]
+    pub fn with_line_style(mut self, style: LineStyle) -> Self {
+        self.style.line_style = Some(style);
+        self
+    }
+
+    /// Wether to display the label of the object defined by this expression
+    #[must_use]
+    pub fn with_display_label(mut self, v: bool) -> Self {
+        self.style.display_label = v;
+        self
+    }
+
+    /// Apply a transformation command to this expression, keeping its style
+    fn transform<const N: usize>(self, command: &'static str, args: [Rc<Node>; N]) -> Self {
+        Self {
+            expr: Node::call(command, [self.expr].into_iter().chain(args)),
+            style: self.style,
+        }
+    }
+
+    /// Check if this expression is a plain real number
+    #[must_use]
+    pub fn is_literal(&self) -> bool {
+        self.literal_value().is_some()
+    }
+
+    /// Value of this expression if it's a plain real number
+    fn literal_value(&self) -> Option<f64> {
+        let Node::Text(expr) = &*self.expr else {
+            return None;
+        };
+
+        expr.strip_suffix(" + 0i")
+            .unwrap_or(expr)
+            .parse::<f64>()
+            .ok()
+    }
+}
+
+pub trait Expr: Into<Expression> {
+    /// Target primitive type.
+    type Target;
+
+    /// Get the element type of this expression
+    fn get_type() -> ElementType;
+
+    /// Create a variable of target type
+    #[must_use]
+    fn var(expr: String) -> Var<Self::Target>;
+}
+
+impl<X: Into<Numeric>, Y: Into<Numeric>> From<(X, Y)> for Expression {
+    fn from((x, y): (X, Y)) -> Self {
+        Self {
+            expr: Node::template("(real({0}), real({1}))", [x.into().0.expr, y.into().0.expr]),
+            style: Style::default(),
+        }
+    }
+}
+
+impl From<f64> for Expression {
+    fn from(value: f64) -> Self {
+        Self {
+            expr: Node::text(format!("{value} + 0i")),
+            style: Style::default(),
+        }
+    }
+}
+
+impl<T> From<Var<T>> for Expression {
+    fn from(value: Var<T>) -> Self {
+        Self::from(&value)
+    }
+}
+
+impl<T> From<&Var<T>> for Expression {
+    fn from(value: &Var<T>) -> Self {
+        Self {
+            expr: Rc::new(Node::Label(Rc::clone(&value.0))),
+            style: Style::default(),
+        }
+    }
+}
+
+impl From<Point> for Expression {
+    fn from(value: Point) -> Self {
+        value.0
+    }
+}
+
+/// A point element of the Geogebra construction
+#[derive(Clone)]
+pub struct Point(Expression);
+
+impl Point {
+    /// Set the point's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
+    }
+
+    /// Wether to display this point's label
+    pub fn set_display_label(&mut self, v: bool) {
+        self.0.style.display_label = v;
+    }
+
+    /// Set the size of this point's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
+    }
+
+    /// Set whether this point's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
+    }
+
+    /// Style for a point bound to its expression
+    #[must_use]
+    fn bound() -> Style {
+        Style {
+            display_label: true,
+            line_style: None,
+            color: Some(ObjColorType {
+                r: 97,
+                g: 97,
+                b: 97,
+                alpha: None,
+            }),
+            font: None,
+            start_point: None,
+            latex: false,
+        }
+    }
+
+    /// Style for a point bound to its expression
+    #[must_use]
+    fn free() -> Style {
+        Style {
+            display_label: true,
+            line_style: None,
+            color: Some(ObjColorType {
+                r: 21,
+                g: 101,
+                b: 192,
+                alpha: None,
+            }),
+            font: None,
+            start_point: None,
+            latex: false,
+        }
+    }
+
+    /// Intersection of two lines
+    #[must_use]
+    pub fn intersect(k: impl Into<Line>, l: impl Into<Line>) -> Self {
+        Self(Expression {
+            expr: Node::call("Intersect", [k.into().0.expr, l.into().0.expr]),
+            style: Self::bound(),
+        })
+    }
+
+    /// One of the (up to two) intersection points of two circles. `index` starts at 1.
+    #[must_use]
+    pub fn intersect_circles(a: impl Into<Conic>, b: impl Into<Conic>, index: u32) -> Self {
+        Self(Expression {
+            expr: Node::call(
+                "Intersect",
+                [a.into().0.expr, b.into().0.expr, Node::text(index)],
+            ),
+            style: Self::bound(),
+        })
+    }
+
+    /// One of the (up to two) points where a tangent from `from` touches a conic. `index` starts at 1.
+    #[must_use]
+    pub fn tangent_point(from: impl Into<Point>, to: impl Into<Conic>, index: u32) -> Self {
+        let to = to.into().0.expr;
+
+        // The touch points are where the polar line of `from` meets the conic.
+        Self(Expression {
+            expr: Node::template(
+                "Intersect(Polar({0}, {1}), {1}, {2})",
+                [from.into().0.expr, to, Node::text(index)],
+            ),
+            style: Self::bound(),
+        })
+    }
+
+    /// Point on another geometric object
+    #[must_use]
+    pub fn on(v: impl Object) -> Self {
+        Self(Expression {
+            expr: Node::call("Point", [v.into().expr]),
+            style: Self::free(),
+        })
+    }
+
+    /// Reflect a point over another point (point symmetry), styled like the original
+    #[must_use]
+    pub fn reflect_over_point(p: impl Into<Point>, center: impl Into<Point>) -> Self {
+        Self(p.into().0.transform("Reflect", [center.into().0.expr]))
+    }
+
+    /// Point on a circle at a given angle, measured from the positive x direction
+    #[must_use]
+    pub fn on_circle_at(circle: impl Into<Conic>, angle: impl Into<Numeric>) -> Self {
+        let circle = circle.into().0.expr;
+        let angle = angle.into().0.expr;
+
+        Self(Expression {
+            expr: Node::template(
+                "Center({0}) + (Radius({0}) cos(real({1})), Radius({0}) sin(real({1})))",
+                [circle, angle],
+            ),
+            style: Self::bound(),
+        })
+    }
+
+    /// Midpoint of two points
+    #[must_use]
+    pub fn midpoint(a: impl Into<Point>, b: impl Into<Point>) -> Self {
+        Self(Expression {
+            expr: Node::call("Midpoint", [a.into().0.expr, b.into().0.expr]),
+            style: Self::bound(),
+        })
+    }
+
+    /// A classic center of the triangle ABC
+    #[must_use]
+    pub fn triangle_center(
+        a: impl Into<Point>,
+        b: impl Into<Point>,
+        c: impl Into<Point>,
+        kind: TriangleCenter,
+    ) -> Self {
+        Self(Expression {
+            expr: Node::call(
+                "TriangleCenter",
+                [
+                    a.into().0.expr,
+                    b.into().0.expr,
+                    c.into().0.expr,
+                    Node::text(kind.kimberling_index()),
+                ],
+            ),
+            style: Self::bound(),
+        })
+    }
+
+    /// Point from a complex number
+    #[must_use]
+    pub fn from_complex(z: impl Into<Numeric>) -> Self {
+        Self(Expression {
+            expr: Node::call("ToPoint", [z.into().0.expr]),
+            style: Self::bound(),
+        })
+    }
+
+    /// Weighted average of points (barycenter).
+    /// Returns `None` if the weights don't add up to a finite, nonzero total.
+    #[must_use]
+    pub fn weighted_centroid(points: &[(impl Into<Point> + Clone, f64)]) -> Option<Self> {
+        let total: f64 = points.iter().map(|(_, weight)| weight).sum();
+
+        if total == 0.0 || !total.is_finite() {
+            return None;
+        }
+
+        let mut x = Numeric::zero();
+        let mut y = Numeric::zero();
+
+        for (point, weight) in points {
+            let point = point.clone().into();
+            x += point.clone().x() * *weight;
+            y += point.y() * *weight;
+        }
+
+        let mut centroid = Self::from((x / total, y / total));
+        centroid.0.style = Self::bound();
+        Some(centroid)
+    }
+
+    /// Get the x coordinate of this point
+    #[must_use]
+    pub fn x(self) -> Numeric {
+        Numeric(Expression {
+            expr: Node::call("x", [self.0.expr]),
+            style: Style::default(),
+        })
+    }
+
+    /// Get the y coordinate of this point
+    #[must_use]
+    pub fn y(self) -> Numeric {
+        Numeric(Expression {
+            expr: Node::call("y", [self.0.expr]),
+            style: Style::default(),
+        })
+    }
+
+    /// Convert this point to a complex number
+    #[must_use]
+    pub fn complex(self) -> Numeric {
+        Numeric(Expression::new(Node::call("ToComplex", [self.0.expr])))
+    }
+
+    /// Mirror image of this point across the x axis, styled like the original
+    #[must_use]
+    pub fn reflect_x(self) -> Point {
+        Point(Expression {
+            expr: Node::template("(x({0}), -y({0}))", [self.0.expr]),
+            style: self.0.style,
+        })
+    }
+
+    /// Mirror image of this point across the y axis, styled like the original
+    #[must_use]
+    pub fn reflect_y(self) -> Point {
+        Point(Expression {
+            expr: Node::template("(-x({0}), y({0}))", [self.0.expr]),
+            style: self.0.style,
+        })
+    }
+
+    /// Mirror image of this point across a line, styled like the original
+    #[must_use]
+    pub fn reflect(self, line: impl Into<Line>) -> Self {
+        Self(self.0.transform("Reflect", [line.into().0.expr]))
+    }
+
+    /// This point rotated counterclockwise around a point, styled like the original
+    #[must_use]
+    pub fn rotate(self, angle: impl Into<Numeric>, center: impl Into<Point>) -> Self {
+        Self(
+            self.0
+                .transform("Rotate", [angle.into().0.expr, center.into().0.expr]),
+        )
+    }
+
+    /// This point moved by a vector, styled like the original
+    #[must_use]
+    pub fn translate(self, v: impl Into<Vector>) -> Self {
+        Self(self.0.transform("Translate", [v.into().0.expr]))
+    }
+}
+
+/// A center of a triangle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriangleCenter {
+    /// Intersection of angle bisectors
+    Incenter,
+    /// Intersection of medians
+    Centroid,
+    /// Intersection of perpendicular bisectors
+    Circumcenter,
+    /// Intersection of altitudes
+    Orthocenter,
+}
+
+impl TriangleCenter {
+    /// Index of this center in Clark Kimberling's encyclopedia, used by Geogebra.
+    #[must_use]
+    pub fn kimberling_index(self) -> u32 {
+        match self {
+            Self::Incenter => 1,
+            Self::Centroid => 2,
+            Self::Circumcenter => 3,
+            Self::Orthocenter => 4,
+        }
+    }
+}
+
+impl<X: Into<Numeric>, Y: Into<Numeric>> From<(X, Y)> for Point {
+    fn from(value: (X, Y)) -> Self {
+        Self(Expression::from(value))
+    }
+}
+
+impl From<Expression> for Point {
+    fn from(value: Expression) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Var<Point>> for Point {
+    fn from(value: Var<Point>) -> Self {
+        Self(Expression::from(value))
+    }
+}
+
+impl From<&Var<Point>> for Point {
+    fn from(value: &Var<Point>) -> Self {
+        Self(Expression::from(value))
+    }
+}
+
+impl Expr for Point {
+    type Target = Self;
+
+    fn get_type() -> ElementType {
+        ElementType::Point
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
+    }
+}
+
+impl Object for Point {}
+
+impl<T: Into<Vector>> Add<T> for Point {
+    type Output = Self;
+
+    fn add(mut self, rhs: T) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Into<Vector>> AddAssign<T> for Point {
+    fn add_assign(&mut self, rhs: T) {
+        let expr = Expression {
+            expr: Rc::new(Node::Binary(
+                Rc::clone(&self.0.expr),
+                "+",
+                rhs.into().0.expr,
+            )),
+            style: Self::bound(),
+        };
+        self.0 = expr;
+    }
+}
+
+impl<X: Into<Numeric>, Y: Into<Numeric>> Expr for (X, Y) {
+    type Target = Point;
+
+    fn get_type() -> ElementType {
+        ElementType::Point
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
+    }
+}
+
+/// Trait with point-related functions
+pub trait PointAccess: Sized
+where
+    Point: From<Self>,
+{
+    /// Get the x coordinate of this point
+    #[must_use]
+    fn x(self) -> Numeric {
+        Point::from(self).x()
+    }
+
+    /// Get the y coordinate of this point
+    #[must_use]
+    fn y(self) -> Numeric {
+        Point::from(self).x()
+    }
+
+    /// Convert to a complex number
+    #[must_use]
+    fn complex(self) -> Numeric {
+        Point::from(self).complex()
+    }
+
+    /// Mirror image across the x axis
+    #[must_use]
+    fn reflect_x(self) -> Point {
+        Point::from(self).reflect_x()
+    }
+
+    /// Mirror image across the y axis
+    #[must_use]
+    fn reflect_y(self) -> Point {
+        Point::from(self).reflect_y()
+    }
+}
+
+impl<T> PointAccess for T where Point: From<T> {}
+
+impl Expr for f64 {
+    type Target = Numeric;
+
+    fn get_type() -> ElementType {
+        ElementType::Numeric
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
+    }
+}
+
+/// A line element of the Geogebra construction
+#[derive(Clone)]
+pub struct Line(Expression);
+
+impl Line {
+    /// Set the line's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
+    }
+
+    /// Set the line's style
+    pub fn set_style(&mut self, style: LineStyle) {
+        self.0.style.line_style = Some(style);
+    }
+
+    /// Make the line dashed
+    pub fn set_dashed(&mut self) {
+        self.0.style.set_line_type(LineType::DashedLong);
+    }
+
+    /// Make the line dotted
+    pub fn set_dotted(&mut self) {
+        self.0.style.set_line_type(LineType::Dotted);
+    }
+
+    /// Set the line's opacity. The value is clamped to `[0, 1]` and ignored if it's not finite.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.0.style.set_opacity(opacity);
+    }
+
+    /// Wether to display this line's label
+    pub fn set_display_label(&mut self, v: bool) {
+        self.0.style.display_label = v;
+    }
+
+    /// Set the size of this line's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
+    }
+
+    /// Set whether this line's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
+    }
+
+    /// Default line style.
+    #[must_use]
+    fn style() -> Style {
+        Style {
+            display_label: false,
+            line_style: Some(LineStyle::default()),
+            color: None,
+            font: None,
+            start_point: None,
+            latex: false,
+        }
+    }
+
+    /// Make a line through two points.
+    #[must_use]
+    pub fn new(a: impl Into<Point>, b: impl Into<Point>) -> Self {
+        Self(Expression {
+            expr: Node::call("Line", [a.into().0.expr, b.into().0.expr]),
+            style: Self::style(),
+        })
+    }
+
+    /// Make a line from a point and a direction vector
+    #[must_use]
+    pub fn point_vector(point: impl Into<Point>, vector: impl Into<Numeric>) -> Self {
+        Self(Expression {
+            expr: Node::call("Line", [point.into().0.expr, vector.into().0.expr]),
+            style: Self::style(),
+        })
+    }
+
+    /// Make a line through a point, along a vector
+    #[must_use]
+    pub fn from_vector(origin: impl Into<Point>, v: impl Into<Vector>) -> Self {
+        Self(Expression {
+            expr: Node::call("Line", [origin.into().0.expr, v.into().0.expr]),
+            style: Self::style(),
+        })
+    }
+
+    /// Bisector of an angle
+    #[must_use]
+    pub fn angle_bisector(a: impl Into<Point>, b: impl Into<Point>, c: impl Into<Point>) -> Self {
+        Self(Expression::new(Node::call(
+            "AngleBisector",
+            [a.into().0.expr, b.into().0.expr, c.into().0.expr],
+        )))
+    }
+
+    /// Both bisectors of the angles between two intersecting lines.
+    /// `AngleBisector` has two outputs, but only the first one is kept when it's
+    /// used inside an expression, so the bisectors are built from the lines' directions.
+    #[must_use]
+    pub fn angle_bisector_lines(k: impl Into<Line>, l: impl Into<Line>) -> List<Line> {
+        let k = k.into().0.expr;
+        let l = l.into().0.expr;
+
+        List(
+            Expression::new(Node::template(
+                "{{Line(Intersect({0}, {1}), UnitVector({0}) + UnitVector({1})), \
+                Line(Intersect({0}, {1}), UnitVector({0}) - UnitVector({1}))}}",
+                [k, l],
+            )),
+            PhantomData,
+        )
+    }
+
+    /// A line perpendicular to another, going through a point
+    #[must_use]
+    pub fn perpendicular(to: impl Into<Line>, through: impl Into<Point>) -> Self {
+        Self(Expression::new(Node::call(
+            "PerpendicularLine",
+            [through.into().0.expr, to.into().0.expr],
+        )))
+    }
+
+    /// A line parallel to another, going through a point
+    #[must_use]
+    pub fn parallel(to: impl Into<Line>, through: impl Into<Point>) -> Self {
+        Self(Expression::new(Node::call(
+            "Line",
+            [through.into().0.expr, to.into().0.expr],
+        )))
+    }
+
+    /// Direction vector of this line
+    #[must_use]
+    pub fn direction(self) -> Numeric {
+        Numeric(Expression::new(Node::call("Direction", [self.0.expr])))
+    }
+
+    /// Line of best fit through a list of points
+    #[must_use]
+    pub fn fit(points: impl Into<List<Point>>) -> Self {
+        Self(Expression::new(Node::call(
+            "FitLine",
+            [points.into().0.expr],
+        )))
+    }
+
+    /// Slope of this line
+    #[must_use]
+    pub fn slope(self) -> Numeric {
+        Numeric(Expression::new(Node::call("Slope", [self.0.expr])))
+    }
+
+    /// The y coordinate at which this line crosses the y axis
+    #[must_use]
+    pub fn y_intercept(self) -> Numeric {
+        Numeric(Expression::new(Node::template(
+            "y(Intersect({0}, yAxis))",
+            [self.0.expr],
+        )))
+    }
+
+    /// The region below this line. Not defined for vertical lines.
+    #[must_use]
+    pub fn half_plane_below(self) -> Inequality {
+        Inequality(Expression {
+            expr: Node::template(
+                "y < {0} x + {1}",
+                [self.clone().slope().0.expr, self.y_intercept().0.expr],
+            ),
+            style: Inequality::style(),
+        })
+    }
+
+    /// The region above this line. Not defined for vertical lines.
+    #[must_use]
+    pub fn half_plane_above(self) -> Inequality {
+        Inequality(Expression {
+            expr: Node::template(
+                "y > {0} x + {1}",
+                [self.clone().slope().0.expr, self.y_intercept().0.expr],
+            ),
+            style: Inequality::style(),
+        })
+    }
+
+    /// Ray along this line, starting at a point
+    #[must_use]
+    pub fn to_ray(self, from: impl Into<Point>) -> Ray {
+        Ray(Expression::new(Node::template(
+            "Ray({0}, Direction({1}))",
+            [from.into().0.expr, self.0.expr],
+        )))
+    }
+
+    /// Mirror image of this line across a line, styled like the original
+    #[must_use]
+    pub fn reflect(self, line: impl Into<Line>) -> Self {
+        Self(self.0.transform("Reflect", [line.into().0.expr]))
+    }
+
+    /// This line rotated counterclockwise around a point, styled like the original
+    #[must_use]
+    pub fn rotate(self, angle: impl Into<Numeric>, center: impl Into<Point>) -> Self {
+        Self(
+            self.0
+                .transform("Rotate", [angle.into().0.expr, center.into().0.expr]),
+        )
+    }
+
+    /// This line moved by a vector, styled like the original
+    #[must_use]
+    pub fn translate(self, v: impl Into<Vector>) -> Self {
+        Self(self.0.transform("Translate", [v.into().0.expr]))
+    }
+}
+
+impl From<Expression> for Line {
+    fn from(value: Expression) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Var<Line>> for Line {
+    fn from(value: Var<Line>) -> Self {
+        Self(Expression::from(value))
+    }
+}
+
+impl From<&Var<Line>> for Line {
+    fn from(value: &Var<Line>) -> Self {
+        Self(Expression::from(value))
+    }
+}
+
+impl From<Line> for Expression {
+    fn from(value: Line) -> Self {
+        value.0
+    }
+}
+
+impl Expr for Line {
+    type Target = Self;
+
+    fn get_type() -> ElementType {
+        ElementType::Line
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
+    }
+}
+
+impl Object for Line {}
+
+/// Trait with line-related functions
+pub trait LineAccess: Sized
+where
+    Line: From<Self>,
+{
+    /// Direction vector of this line
+    #[must_use]
+    fn direction(self) -> Numeric {
+        Line::from(self).direction()
+    }
+
+    /// Ray along this line, starting at a point
+    #[must_use]
+    fn to_ray(self, from: impl Into<Point>) -> Ray {
+        Line::from(self).to_ray(from)
+    }
+
+    /// Slope of this line
+    #[must_use]
+    fn slope(self) -> Numeric {
+        Line::from(self).slope()
+    }
+
+    /// The y coordinate at which this line crosses the y axis
+    #[must_use]
+    fn y_intercept(self) -> Numeric {
+        Line::from(self).y_intercept()
+    }
+
+    /// The region below this line
+    #[must_use]
+    fn half_plane_below(self) -> Inequality {
+        Line::from(self).half_plane_below()
+    }
+
+    /// The region above this line
+    #[must_use]
+    fn half_plane_above(self) -> Inequality {
+        Line::from(self).half_plane_above()
+    }
+}
+
+impl<T> LineAccess for T where Line: From<T> {}
+
+/// A list expression
+#[derive(Clone)]
+pub struct List<T>(Expression, PhantomData<T>);
+
+impl<T: Expr, It: IntoIterator<Item = T>> From<It> for List<T::Target>
+where
+    Expression: From<T>,
+{
+    fn from(value: It) -> Self {
+        let items = value
+            .into_iter()
+            .map(|item| Expression::from(item).expr)
+            .collect();
+
+        Self(Expression::new(Rc::new(Node::List(items))), PhantomData)
+    }
+}
+
+impl<T> From<List<T>> for Expression {
+    fn from(value: List<T>) -> Self {
+        value.0
+    }
+}
+
+impl<T> Expr for List<T> {
+    type Target = List<T>;
+
+    fn get_type() -> ElementType {
+        ElementType::List
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
+    }
+}
+
+impl<T> From<Expression> for List<T> {
+    fn from(value: Expression) -> Self {
+        Self(value, PhantomData)
+    }
+}
+
+impl<T> From<Var<List<T>>> for List<T> {
+    fn from(value: Var<List<T>>) -> Self {
+        Self(value.into(), PhantomData)
+    }
+}
+
+impl<T> From<&Var<List<T>>> for List<T> {
+    fn from(value: &Var<List<T>>) -> Self {
+        Self(value.into(), PhantomData)
+    }
+}
+
+impl<T: From<Expression>> List<T> {
+    /// Element at a 1-based index, which may be any expression
+    #[must_use]
+    pub fn get(self, index: impl Into<Numeric>) -> T {
+        T::from(Expression::new(Node::call(
+            "Element",
+            [self.0.expr, index.into().0.expr],
+        )))
+    }
+
+    /// Element at a 0-based index
+    #[must_use]
+    pub fn at(self, index: usize) -> T {
+        T::from(Expression::new(Node::call(
+            "Element",
+            [self.0.expr, Node::text(index + 1)],
+        )))
+    }
+}
+
+impl List<Point> {
+    /// Both intersection points of a line and a conic, picked one by one
+    /// because `Intersect` only keeps its first output inside an expression
+    #[must_use]
+    pub fn line_conic_intersections(line: impl Into<Line>, conic: impl Into<Conic>) -> Self {
+        Self(
+            Expression::new(Node::template(
+                "Sequence(Intersect({0}, {1}, {2}), {2}, 1, 2)",
+                [
+                    line.into().0.expr,
+                    conic.into().0.expr,
+                    Node::text(INDEX_VARIABLE),
+                ],
+            )),
+            PhantomData,
+        )
+    }
+
+    /// Mean value of X coordinates of points.
+    #[must_use]
+    pub fn mean_x(self) -> Numeric {
+        Numeric(Expression {
+            expr: Node::call("MeanX", [self.0.expr]),
+            style: Style::default(),
+        })
+    }
+
+    /// Mean value of Y coordinates of points.
+    #[must_use]
+    pub fn mean_y(self) -> Numeric {
+        Numeric(Expression {
+            expr: Node::call("MeanX", [self.0.expr]),
+            style: Style::default(),
+        })
+    }
+}
+
+impl List<Numeric> {
+    /// Numbers from `from` to `to` (inclusive), separated by `step`
+    #[must_use]
+    pub fn range(from: f64, to: f64, step: f64) -> Self {
+        Self(
+            Expression::expr(format!("Sequence({from}, {to}, {step})")),
+            PhantomData,
+        )
+    }
+
+    /// Sum of these numbers
+    #[must_use]
+    pub fn sum(self) -> Numeric {
+        Numeric(Expression {
+            expr: Node::template("Sum(Append({0}, 0 + 0i))", [self.0.expr]),
+            style: Style::default(),
+        })
+    }
+
+    /// Product of these numbers
+    #[must_use]
+    pub fn product(self) -> Numeric {
+        Numeric(Expression {
+            expr: Node::template("Product(Append({0}, 1 + 0i))", [self.0.expr]),
+            style: Style::default(),
+        })
+    }
+
+    /// Arithmetic mean of these numbers
+    #[must_use]
+    pub fn mean(self) -> Numeric {
+        Numeric(Expression::new(Node::call("Mean", [self.0.expr])))
+    }
+
+    /// Median of these numbers
+    #[must_use]
+    pub fn median(self) -> Numeric {
+        Numeric(Expression::new(Node::call("Median", [self.0.expr])))
+    }
+
+    /// Population standard deviation of these numbers
+    #[must_use]
+    pub fn stdev(self) -> Numeric {
+        Numeric(Expression::new(Node::call("SD", [self.0.expr])))
+    }
+
+    /// Population variance of these numbers
+    #[must_use]
+    pub fn variance(self) -> Numeric {
+        Numeric(Expression::new(Node::call("Variance", [self.0.expr])))
+    }
+}
+
+/// A trait for accessing list functions through convertible types
+pub trait ListAccess<T>: Sized
+where
+    List<T>: From<Self>,
+{
+    /// Get an element by its 0-based index
+    fn at(self, index: usize) -> T
+    where
+        T: From<Expression>,
+    {
+        List::from(self).at(index)
+    }
+
+    /// Get the mean X coordinate
+    fn mean_x(self) -> Numeric
+    where
+        List<Point>: From<Self>,
+    {
+        List::from(self).mean_x()
+    }
+
+    /// Get the mean Y coordinate
+    fn mean_y(self) -> Numeric
+    where
+        List<Point>: From<Self>,
+    {
+        List::from(self).mean_y()
+    }
+
+    /// Get the sum of numbers
+    fn sum(self) -> Numeric
+    where
+        List<Numeric>: From<Self>,
+    {
+        List::from(self).sum()
+    }
+
+    /// Get the product of numbers
+    fn product(self) -> Numeric
+    where
+        List<Numeric>: From<Self>,
+    {
+        List::from(self).product()
+    }
+
+    /// Get the mean of numbers
+    fn mean(self) -> Numeric
+    where
+        List<Numeric>: From<Self>,
+    {
+        List::from(self).mean()
+    }
+
+    /// Get the median of numbers
+    fn median(self) -> Numeric
+    where
+        List<Numeric>: From<Self>,
+    {
+        List::from(self).median()
+    }
+
+    /// Get the standard deviation of numbers
+    fn stdev(self) -> Numeric
+    where
+        List<Numeric>: From<Self>,
+    {
+        List::from(self).stdev()
+    }
+
+    /// Get the variance of numbers
+    fn variance(self) -> Numeric
+    where
+        List<Numeric>: From<Self>,
+    {
+        List::from(self).variance()
+    }
+}
+
+impl<T, V> ListAccess<T> for V where List<T>: From<V> {}
+
+/// A number value
+#[derive(Clone)]
+pub struct Numeric(Expression);
+
+impl Numeric {
+    /// Check if this numeric is a constant
+    #[must_use]
+    pub fn is_const(&self) -> bool {
+        self.0.is_literal()
+    }
+
+    /// Value of this numeric if it's a real constant
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        self.0.literal_value()
+    }
+
+    /// Combine two constants into one, unless the result is not finite.
+    fn fold(&self, rhs: &Numeric, op: impl FnOnce(f64, f64) -> f64) -> Option<Numeric> {
+        let value = op(self.as_f64()?, rhs.as_f64()?);
+        value.is_finite().then(|| Numeric::from(value))
+    }
+
+    /// A random number between 0 and 1, rerolled on every refresh
+    #[must_use]
+    pub fn random() -> Self {
+        Self(Expression::expr("random()"))
+    }
+
+    /// A random integer between `min` and `max` (inclusive), rerolled on every refresh
+    #[must_use]
+    pub fn random_between(min: impl Into<Numeric>, max: impl Into<Numeric>) -> Self {
+        Self(Expression::new(Node::template(
+            "RandomBetween(real({0}), real({1}))",
+            [min.into().0.expr, max.into().0.expr],
+        )))
+    }
+
+    /// The number pi
+    #[must_use]
+    pub fn pi() -> Self {
+        Self(Expression::expr("pi"))
+    }
+
+    /// The `x` coordinate variable, for use in equations
+    #[must_use]
+    pub fn var_x() -> Self {
+        Self(Expression::expr("x"))
+    }
+
+    /// The `y` coordinate variable, for use in equations
+    #[must_use]
+    pub fn var_y() -> Self {
+        Self(Expression::expr("y"))
+    }
+
+    /// Distance between a point and an object
+    #[must_use]
+    pub fn distance<T: Object>(point: impl Into<Point>, object: T) -> Self {
+        Self(Expression {
+            expr: Node::call("Distance", [point.into().0.expr, object.into().expr]),
+            style: Style::default(),
+        })
+    }
+
+    /// Power of a point with respect to a circle.
+    /// Positive outside the circle, where it's the squared length of the tangents.
+    #[must_use]
+    pub fn power_of_point(p: impl Into<Point>, c: impl Into<Conic>) -> Self {
+        let c = c.into();
+        let distance = Self::distance(p, c.clone().center());
+
+        Self(Expression::new(Node::template(
+            "({0})^2 - ({1})^2",
+            [distance.0.expr, c.radius().0.expr],
+        )))
+    }
+
+    /// A complex number
+    #[must_use]
+    pub fn complex(real: impl Into<Numeric>, imaginary: impl Into<Numeric>) -> Self {
+        Self(Expression {
+            expr: Node::template(
+                "({0}) + ({1})i",
+                [real.into().0.expr, imaginary.into().0.expr],
+            ),
+            style: Style::default(),
+        })
+    }
+
+    /// An angle defined by three points
+    #[must_use]
+    pub fn angle(a: impl Into<Point>, b: impl Into<Point>, c: impl Into<Point>) -> Self {
+        Self(Expression::new(Node::call(
+            "Angle",
+            [a.into().0.expr, b.into().0.expr, c.into().0.expr],
+        )))
+    }
+
+    /// An angle defined by three points, as a plain number of degrees
+    #[must_use]
+    pub fn angle_deg(a: impl Into<Point>, b: impl Into<Point>, c: impl Into<Point>) -> Self {
+        Self(Expression::new(Node::template(
+            "Angle({0}, {1}, {2}) / °",
+            [a.into().0.expr, b.into().0.expr, c.into().0.expr],
+        )))
+    }
+
+    /// A reflex angle (at least 180 degrees) defined by three points
+    #[must_use]
+    pub fn angle_reflex(a: impl Into<Point>, b: impl Into<Point>, c: impl Into<Point>) -> Self {
+        let (a, b, c) = (a.into().0.expr, b.into().0.expr, c.into().0.expr);
+
+        Self(Expression::new(Node::template(
+            "2pi - Min(Angle({0}, {1}, {2}), Angle({2}, {1}, {0}))",
+            [a, b, c],
+        )))
+    }
+
+    /// An angle defined by three points, measured clockwise
+    #[must_use]
+    pub fn angle_clockwise(a: impl Into<Point>, b: impl Into<Point>, c: impl Into<Point>) -> Self {
+        Self(Expression::new(Node::call(
+            "Angle",
+            [c.into().0.expr, b.into().0.expr, a.into().0.expr],
+        )))
+    }
+
+    /// Angle between two lines
+    #[must_use]
+    pub fn angle_lines(k: impl Into<Line>, l: impl Into<Line>) -> Self {
+        Self(Expression::new(Node::call(
+            "Angle",
+            [k.into().0.expr, l.into().0.expr],
+        )))
+    }
+
+    /// atan2 function
+    #[must_use]
+    pub fn atan2(y: impl Into<Numeric>, x: impl Into<Numeric>) -> Self {
+        Self(Expression::new(Node::template(
+            "atan2({0}, {1})",
+            [y.into().0.expr, x.into().0.expr],
+        )))
+    }
+
+    /// Sum of an expression over a range of a variable, without building a list.
+    /// The variable is local to the expression.
+    ///
+    /// # Errors
+    /// Returns an error if `var` isn't a valid label or is a reserved word.
+    pub fn sum_range(
+        expr: impl Fn(Var<Numeric>) -> Numeric,
+        var: impl ToString,
+        from: impl Into<Numeric>,
+        to: impl Into<Numeric>,
+    ) -> Result<Self, LabelError> {
+        let var = var.to_string();
+        validate_label(&var)?;
+
+        Ok(Self(Expression::new(Node::template(
+            "Sum({0}, {3}, real({1}), real({2}))",
+            [
+                expr(Var::new(var.clone())).0.expr,
+                from.into().0.expr,
+                to.into().0.expr,
+                Node::text(var),
+            ],
+        ))))
+    }
+
+    /// Product of an expression over a range of a variable, without building a list.
+    /// The variable is local to the expression.
+    ///
+    /// # Errors
+    /// Returns an error if `var` isn't a valid label or is a reserved word.
+    pub fn product_range(
+        expr: impl Fn(Var<Numeric>) -> Numeric,
+        var: impl ToString,
+        from: impl Into<Numeric>,
+        to: impl Into<Numeric>,
+    ) -> Result<Self, LabelError> {
+        let var = var.to_string();
+        validate_label(&var)?;
+
+        Ok(Self(Expression::new(Node::template(
+            "Product({0}, {3}, real({1}), real({2}))",
+            [
+                expr(Var::new(var.clone())).0.expr,
+                from.into().0.expr,
+                to.into().0.expr,
+                Node::text(var),
+            ],
+        ))))
+    }
+
+    /// Raise this number to a power.
+    #[must_use]
+    pub fn pow(self, exponent: impl Into<Numeric>) -> Self {
+        Self(Expression::new(Node::template(
+            "({0})^({1})",
+            [self.0.expr, exponent.into().0.expr],
+        )))
+    }
+
+    /// This number as a percentage, for a proportion between 0 and 1
+    #[must_use]
+    pub fn percent(self) -> Self {
+        Self(Expression::new(Node::template(
+            "({0}) * 100",
+            [self.0.expr],
+        )))
+    }
+
+    /// Get the real `n`-th root of this number. Unlike [`Numeric::pow`], defined for negative numbers and odd `n`.
+    #[must_use]
+    pub fn nth_root(self, n: impl Into<Numeric>) -> Self {
+        Self(Expression::new(Node::template(
+            "nroot(real({0}), real({1}))",
+            [self.0.expr, n.into().0.expr],
+        )))
+    }
+
+    /// Get the real cube root of this number
+    #[must_use]
+    pub fn cbrt(self) -> Self {
+        Self(Expression::new(Node::template(
+            "cbrt(real({0}))",
+            [self.0.expr],
+        )))
+    }
+
+    /// Get the real part of this number
+    #[must_use]
+    pub fn real(self) -> Self {
+        Self(Expression::new(Node::call("real", [self.0.expr])))
+    }
+
+    /// Get the imaginary part of this number
+    #[must_use]
+    pub fn imaginary(self) -> Self {
+        Self(Expression::new(Node::call("imaginary", [self.0.expr])))
+    }
+
+    /// Absolute value of the real part. The imaginary part is ignored.
+    #[must_use]
+    pub fn abs(self) -> Self {
+        Self(Expression::new(Node::template(
+            "abs(real({0}))",
+            [self.0.expr],
+        )))
+    }
+
+    /// Modulus (magnitude) of this complex number
+    #[must_use]
+    pub fn modulus(self) -> Self {
+        Self(Expression::new(Node::call("abs", [self.0.expr])))
+    }
+
+    /// Complex conjugate of this number
+    #[must_use]
+    pub fn conjugate(self) -> Self {
+        Self(Expression::new(Node::call("conjugate", [self.0.expr])))
+    }
+
+    /// Natural logarithm (base e)
+    #[must_use]
+    pub fn ln(self) -> Self {
+        Self(Expression::new(Node::call("ln", [self.0.expr])))
+    }
+
+    /// Exponential function (e^this)
+    #[must_use]
+    pub fn exp(self) -> Self {
+        Self(Expression::new(Node::call("exp", [self.0.expr])))
+    }
+
+    /// Get the argument of a complex number.
+    #[must_use]
+    pub fn arg(self) -> Self {
+        Self(Expression::new(Node::call("arg", [self.0.expr])))
+    }
+
+    /// Convert this to a point
+    #[must_use]
+    pub fn point(self) -> Point {
+        Point(Expression::new(Node::call("ToPoint", [self.0.expr])))
+    }
+
+    /// Get the sine of this angle.
+    #[must_use]
+    pub fn sin(self) -> Numeric {
+        Numeric(Expression::new(Node::call("sin", [self.0.expr])))
+    }
+
+    /// Get the cosine of this angle.
+    #[must_use]
+    pub fn cos(self) -> Numeric {
+        Numeric(Expression::new(Node::call("cos", [self.0.expr])))
+    }
+
+    /// Get the arcsine of this angle.
+    #[must_use]
+    pub fn asin(self) -> Numeric {
+        Numeric(Expression::new(Node::call("asin", [self.0.expr])))
+    }
+
+    /// Get the arccosine of this angle.
+    #[must_use]
+    pub fn acos(self) -> Numeric {
+        Numeric(Expression::new(Node::call("acos", [self.0.expr])))
+    }
+
+    /// Get the arctan of this angle.
+    #[must_use]
+    pub fn atan(self) -> Numeric {
+        Numeric(Expression::new(Node::call("atan", [self.0.expr])))
+    }
+
+    /// Get the hyperbolic sine of this number.
+    #[must_use]
+    pub fn sinh(self) -> Numeric {
+        Numeric(Expression::new(Node::call("sinh", [self.0.expr])))
+    }
+
+    /// Get the hyperbolic cosine of this number.
+    #[must_use]
+    pub fn cosh(self) -> Numeric {
+        Numeric(Expression::new(Node::call("cosh", [self.0.expr])))
+    }
+
+    /// Get the hyperbolic tangent of this number.
+    #[must_use]
+    pub fn tanh(self) -> Numeric {
+        Numeric(Expression::new(Node::call("tanh", [self.0.expr])))
+    }
+
+    /// Normalize the value (abs of 1)
+    #[must_use]
+    pub fn normalize(self) -> Numeric {
+        Numeric(Expression::new(Node::call("UnitVector", [self.0.expr])))
+    }
+
+    /// Integer part of the real part, rounding towards zero
+    #[must_use]
+    pub fn trunc(self) -> Numeric {
+        Numeric(Expression::new(Node::template(
+            "sgn(real({0})) floor(abs(real({0})))",
+            [self.0.expr],
+        )))
+    }
+
+    /// Fractional part of the real part (`value - floor(value)`)
+    #[must_use]
+    pub fn fract(self) -> Numeric {
+        Numeric(Expression::new(Node::template(
+            "real({0}) - floor(real({0}))",
+            [self.0.expr],
+        )))
+    }
+
+    /// Euclidean remainder, like [`f64::rem_euclid`]: always in `[0, |b|)`, whatever the signs.
+    /// It's not the floored remainder, which takes the sign of `b` and so is negative
+    /// for a negative `b`. The `%` operator emits GeoGebra's `Mod`, which differs too.
+    #[must_use]
+    pub fn rem_euclid(self, b: impl Into<Numeric>) -> Numeric {
+        let b = b.into();
+
+        if let Some(folded) = self.fold(&b, f64::rem_euclid) {
+            return folded;
+        }
+
+        Numeric(Expression::new(Node::template(
+            "real({0}) - abs(real({1})) floor(real({0}) / abs(real({1})))",
+            [self.0.expr, b.0.expr],
+        )))
+    }
+
+    /// Remainder of division rounding towards zero, like Rust's `%`: has the sign of `self`
+    #[must_use]
+    pub fn truncated_mod(self, b: impl Into<Numeric>) -> Numeric {
+        let b = b.into();
+
+        if let Some(folded) = self.fold(&b, |a, b| a % b) {
+            return folded;
+        }
+
+        Numeric(Expression::new(Node::template(
+            "real({0}) - real({1}) sgn(real({0}) / real({1})) floor(abs(real({0}) / real({1})))",
+            [self.0.expr, b.0.expr],
+        )))
+    }
+}
+
+impl From<f64> for Numeric {
+    fn from(value: f64) -> Self {
+        Self(Expression::from(value))
+    }
+}
+
+/// Raw GeoGebra syntax. The caller is responsible for the expression being valid and numeric.
+impl From<&str> for Numeric {
+    fn from(value: &str) -> Self {
+        Self(Expression::expr(value))
+    }
+}
+
+impl From<i32> for Numeric {
+    fn from(value: i32) -> Self {
+        Self::from(f64::from(value))
+    }
+}
+
+impl From<u32> for Numeric {
+    fn from(value: u32) -> Self {
+        Self::from(f64::from(value))
+    }
+}
+
+impl From<i64> for Numeric {
+    fn from(value: i64) -> Self {
+        Self::from(value as f64)
+    }
+}
+
+impl From<usize> for Numeric {
+    fn from(value: usize) -> Self {
+        Self::from(value as f64)
+    }
+}
+
+impl From<Expression> for Numeric {
+    fn from(value: Expression) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Var<Numeric>> for Numeric {
+    fn from(value: Var<Numeric>) -> Self {
+        Self(Expression::from(value))
+    }
+}
+
+impl From<&Var<Numeric>> for Numeric {
+    fn from(value: &Var<Numeric>) -> Self {
+        Self(Expression::from(value))
+    }
+}
+
+impl From<Numeric> for Expression {
+    fn from(value: Numeric) -> Self {
+        value.0
+    }
+}
+
+impl Expr for Numeric {
+    type Target = Self;
+
+    fn get_type() -> ElementType {
+        ElementType::Numeric
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
+    }
+}
+
+impl<T: Into<Numeric>> Add<T> for Numeric {
+    type Output = Self;
+
+    fn add(mut self, rhs: T) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Into<Numeric>> AddAssign<T> for Numeric {
+    fn add_assign(&mut self, rhs: T) {
+        let rhs = rhs.into();
+
+        if let Some(folded) = self.fold(&rhs, |a, b| a + b) {
+            *self = folded;
+            return;
+        }
+
+        let expr = Expression {
+            expr: Rc::new(Node::Binary(Rc::clone(&self.0.expr), "+", rhs.0.expr)),
+            style: Style::default(),
+        };
+        self.0 = expr;
+    }
+}
+
+impl<T: Into<Numeric>> Sub<T> for Numeric {
+    type Output = Self;
+
+    fn sub(mut self, rhs: T) -> Self::Output {
+        self -= rhs;
+        self
+    }
+}
+
+impl<T: Into<Numeric>> SubAssign<T> for Numeric {
+    fn sub_assign(&mut self, rhs: T) {
+        let rhs = rhs.into();
+
+        if let Some(folded) = self.fold(&rhs, |a, b| a - b) {
+            *self = folded;
+            return;
+        }
+
+        let expr = Expression {
+            expr: Rc::new(Node::Binary(Rc::clone(&self.0.expr), "-", rhs.0.expr)),
+            style: Style::default(),
+        };
+        self.0 = expr;
+    }
+}
+
+impl<T: Into<Numeric>> Mul<T> for Numeric {
+    type Output = Self;
+
+    fn mul(mut self, rhs: T) -> Self::Output {
+        self *= rhs;
+        self
+    }
+}
+
+impl<T: Into<Numeric>> MulAssign<T> for Numeric {
+    fn mul_assign(&mut self, rhs: T) {
+        let rhs = rhs.into();
+
+        if let Some(folded) = self.fold(&rhs, |a, b| a * b) {
+            *self = folded;
+            return;
+        }
+
+        let expr = Expression {
+            expr: Rc::new(Node::Binary(Rc::clone(&self.0.expr), "*", rhs.0.expr)),
+            style: Style::default(),
+        };
+        self.0 = expr;
+    }
+}
+
+impl<T: Into<Numeric>> Div<T> for Numeric {
+    type Output = Self;
+
+    fn div(mut self, rhs: T) -> Self::Output {
+        self /= rhs;
+        self
+    }
+}
+
+impl<T: Into<Numeric>> DivAssign<T> for Numeric {
+    fn div_assign(&mut self, rhs: T) {
+        let rhs = rhs.into();
+
+        if let Some(folded) = self.fold(&rhs, |a, b| a / b) {
+            *self = folded;
+            return;
+        }
+
+        let expr = Expression {
+            expr: Rc::new(Node::Binary(Rc::clone(&self.0.expr), "/", rhs.0.expr)),
+            style: Style::default(),
+        };
+        self.0 = expr;
+    }
+}
+
+impl Neg for Numeric {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self(Expression {
+            expr: Rc::new(Node::Neg(self.0.expr)),
+            style: Style::default(),
+        })
+    }
+}
+
+impl<T: Into<Numeric>> Rem<T> for Numeric {
+    type Output = Numeric;
+
+    fn rem(mut self, rhs: T) -> Self::Output {
+        self %= rhs;
+        self
+    }
+}
+
+impl<T: Into<Numeric>> RemAssign<T> for Numeric {
+    fn rem_assign(&mut self, rhs: T) {
+        let expr = Expression::new(Node::call(
+            "Mod",
+            [Rc::clone(&self.0.expr), rhs.into().0.expr],
+        ));
+        self.0 = expr;
+    }
+}
+
+impl From<&Self> for Numeric {
+    fn from(value: &Self) -> Self {
+        Self(value.0.clone())
+    }
+}
+
+impl<T: Copy + Into<Numeric>> From<&T> for Numeric {
+    fn from(value: &T) -> Self {
+        (*value).into()
+    }
+}
+
+impl<T> PartialEq<T> for Numeric
+where
+    Numeric: for<'a> From<&'a T>,
+{
+    fn eq(&self, other: &T) -> bool {
+        let other = Self::from(other);
+        if self.0.expr == other.0.expr {
+            return true;
+        }
+
+        if let Some(v) = self.as_f64() {
+            if let Some(u) = other.as_f64() {
+                return v.partial_cmp(&u).is_some_and(|v| v.is_eq());
+            }
+        }
+
+        false
+    }
+}
+
+impl<T> PartialOrd<T> for Numeric
+where
+    Numeric: for<'a> From<&'a T>,
+{
+    /// Only constants and identical expressions can be compared.
+    fn partial_cmp(&self, other: &T) -> Option<Ordering> {
+        let other = Self::from(other);
+        if self.0.expr == other.0.expr {
+            return Some(Ordering::Equal);
+        }
+
+        self.as_f64()?.partial_cmp(&other.as_f64()?)
+    }
+}
+
+impl Zero for Numeric {
+    fn zero() -> Self {
+        Self(Expression::expr("0"))
+    }
+
+    /// WARNING: This is not necessarily always precise
+    fn is_zero(&self) -> bool {
+        matches!(&*self.0.expr, Node::Text(text) if text == "0" || text == "0.0")
+    }
+}
+
+impl One for Numeric {
+    fn one() -> Self {
+        Self(Expression::expr("1"))
+    }
+
+    /// WARNING: This is not necessarily always precise
+    fn is_one(&self) -> bool {
+        matches!(&*self.0.expr, Node::Text(text) if text == "1" || text == "1.0")
+    }
+}
+
+impl Bounded for Numeric {
+    fn min_value() -> Self {
+        Self::zero()
+    }
+
+    fn max_value() -> Self {
+        Self(Expression::expr(format!("{} + {}i", f64::MAX, f64::MAX)))
+    }
+}
+
+impl Num for Numeric {
+    type FromStrRadixErr = &'static str;
+
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        if radix != 10 {
+            return Err("Only radix of 10 is supported.");
+        }
+
+        Ok(Self(Expression::expr(str)))
+    }
+}
+
+/// Trait for accessing numeric functions
+///
+/// On plain `f64` values, methods that `f64` also has (like `abs`, `trunc`, `fract`,
+/// `sinh`, `cbrt` or `rem_euclid`) resolve to the inherent ones and return an `f64`:
+/// `2.0.abs()` is not a [`Numeric`]. Convert with [`Numeric::from`] first.
+pub trait NumericAccess: Sized
+where
+    Numeric: From<Self>,
+{
+    /// Raise the number to a power
+    #[must_use]
+    fn pow(self, exponent: impl Into<Numeric>) -> Numeric {
+        Numeric::from(self).pow(exponent)
+    }
+
+    /// The number as a percentage
+    #[must_use]
+    fn percent(self) -> Numeric {
+        Numeric::from(self).percent()
+    }
+
+    /// Get the real `n`-th root of the number
+    #[must_use]
+    fn nth_root(self, n: impl Into<Numeric>) -> Numeric {
+        Numeric::from(self).nth_root(n)
+    }
+
+    /// Get the real cube root of the number
+    #[must_use]
+    fn cbrt(self) -> Numeric {
+        Numeric::from(self).cbrt()
+    }
+
+    /// Get the real part of this number
+    #[must_use]
+    fn real(self) -> Numeric {
+        Numeric::from(self).real()
+    }
+
+    /// Get the imaginary part of this number
+    #[must_use]
+    fn imaginary(self) -> Numeric {
+        Numeric::from(self).imaginary()
+    }
+
+    /// Get the absolute value of the real part
+    #[must_use]
+    fn abs(self) -> Numeric {
+        Numeric::from(self).abs()
+    }
+
+    /// Get this complex number's modulus
+    #[must_use]
+    fn modulus(self) -> Numeric {
+        Numeric::from(self).modulus()
+    }
+
+    /// Get this complex number's conjugate
+    #[must_use]
+    fn conjugate(self) -> Numeric {
+        Numeric::from(self).conjugate()
+    }
+
+    /// Get this complex number's argument
+    #[must_use]
+    fn arg(self) -> Numeric {
+        Numeric::from(self).arg()
+    }
+
+    /// Convert this number to a point
+    #[must_use]
+    fn point(self) -> Point {
+        Numeric::from(self).point()
+    }
+
+    /// Natural logarithm (base e)
+    #[must_use]
+    fn ln(self) -> Numeric {
+        Numeric::from(self).ln()
+    }
+
+    /// Exponential function (e^this)
+    #[must_use]
+    fn exp(self) -> Numeric {
+        Numeric::from(self).exp()
+    }
+
+    /// Get the sine of this angle.
+    #[must_use]
+    fn sin(self) -> Numeric {
+        Numeric::from(self).sin()
+    }
+
+    /// Get the cosine of this angle.
+    #[must_use]
+    fn cos(self) -> Numeric {
+        Numeric::from(self).cos()
+    }
+
+    /// Get the arcsine of this angle.
+    #[must_use]
+    fn asin(self) -> Numeric {
+        Numeric::from(self).asin()
+    }
+
+    /// Get the arccosine of this angle.
+    #[must_use]
+    fn acos(self) -> Numeric {
+        Numeric::from(self).acos()
+    }
+
+    /// Get the arctan of this angle.
+    #[must_use]
+    fn atan(self) -> Numeric {
+        Numeric::from(self).atan()
+    }
+
+    /// Get the hyperbolic sine of this number.
+    #[must_use]
+    fn sinh(self) -> Numeric {
+        Numeric::from(self).sinh()
+    }
+
+    /// Get the hyperbolic cosine of this number.
+    #[must_use]
+    fn cosh(self) -> Numeric {
+        Numeric::from(self).cosh()
+    }
+
+    /// Get the hyperbolic tangent of this number.
+    #[must_use]
+    fn tanh(self) -> Numeric {
+        Numeric::from(self).tanh()
+    }
+
+    /// Normalize this value (abs of 1)
+    #[must_use]
+    fn normalize(self) -> Numeric {
+        Numeric::from(self).normalize()
+    }
+
+    /// Integer part, rounding towards zero
+    #[must_use]
+    fn trunc(self) -> Numeric {
+        Numeric::from(self).trunc()
+    }
+
+    /// Fractional part
+    #[must_use]
+    fn fract(self) -> Numeric {
+        Numeric::from(self).fract()
+    }
+
+    /// Euclidean remainder, always non-negative, even for a negative `b`
+    #[must_use]
+    fn rem_euclid(self, b: impl Into<Numeric>) -> Numeric {
+        Numeric::from(self).rem_euclid(b)
+    }
+
+    /// Remainder with the sign of the dividend
+    #[must_use]
+    fn truncated_mod(self, b: impl Into<Numeric>) -> Numeric {
+        Numeric::from(self).truncated_mod(b)
+    }
+}
+
+impl<T> NumericAccess for T where Numeric: From<Self> {}
+
+/// A circle in the construction
+#[derive(Clone)]
+pub struct Conic(Expression);
+
+impl Conic {
+    /// Set the conic's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
+    }
+
+    /// Set the conic's color, with the opacity of its filling. The opacity is clamped to `[0, 1]`
+    /// and the call is ignored if it's not finite.
+    pub fn set_color_alpha(&mut self, r: u8, g: u8, b: u8, alpha: f64) {
+        self.0.style.set_color_alpha(r, g, b, alpha);
+    }
+
+    /// Set the conic's style
+    pub fn set_style(&mut self, style: LineStyle) {
+        self.0.style.line_style = Some(style);
+    }
+
+    /// Make the conic dashed
+    pub fn set_dashed(&mut self) {
+        self.0.style.set_line_type(LineType::DashedLong);
+    }
+
+    /// Make the conic dotted
+    pub fn set_dotted(&mut self) {
+        self.0.style.set_line_type(LineType::Dotted);
+    }
+
+    /// Set the conic's opacity. The value is clamped to `[0, 1]` and ignored if it's not finite.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.0.style.set_opacity(opacity);
+    }
+
+    /// Wether to display this conic's label
+    pub fn set_display_label(&mut self, v: bool) {
+        self.0.style.display_label = v;
+    }
+
+    /// Set the size of this conic's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
+    }
+
+    /// Set whether this conic's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
+    }
+
+    /// Default style for a conic
+    #[must_use]
+    fn style() -> Style {
+        Style {
+            display_label: false,
+            line_style: None,
+            color: None,
+            font: None,
+            start_point: None,
+            latex: false,
+        }
+    }
+
+    /// Create a conic from an equation `eq = 0` in `x` and `y`
+    #[must_use]
+    pub fn from_equation(eq: impl Into<Numeric>) -> Self {
+        Self(Expression {
+            expr: Node::template("{0} = 0", [eq.into().0.expr]),
+            style: Self::style(),
+        })
+    }
+
+    /// Create a new circle with a center and a radius
+    #[must_use]
+    pub fn circle(center: impl Into<Point>, radius: impl Into<Numeric>) -> Self {
+        Self(Expression {
+            expr: Node::template(
+                "Circle({0}, abs({1}))",
+                [center.into().0.expr, radius.into().0.expr],
+            ),
+            style: Self::style(),
+        })
+    }
+
+    /// Circle around a center with the radius given by a segment's length, like a compass
+    #[must_use]
+    pub fn compass(radius_from: impl Into<Segment>, center: impl Into<Point>) -> Self {
+        Self(Expression {
+            expr: Node::call("Circle", [center.into().0.expr, radius_from.into().0.expr]),
+            style: Self::style(),
+        })
+    }
+
+    /// Mirror image of this conic across a line, styled like the original
+    #[must_use]
+    pub fn reflect(self, line: impl Into<Line>) -> Self {
+        Self(self.0.transform("Reflect", [line.into().0.expr]))
+    }
+
+    /// This conic rotated counterclockwise around a point, styled like the original
+    #[must_use]
+    pub fn rotate(self, angle: impl Into<Numeric>, center: impl Into<Point>) -> Self {
+        Self(
+            self.0
+                .transform("Rotate", [angle.into().0.expr, center.into().0.expr]),
+        )
+    }
+
+    /// This conic moved by a vector, styled like the original
+    #[must_use]
+    pub fn translate(self, v: impl Into<Vector>) -> Self {
+        Self(self.0.transform("Translate", [v.into().0.expr]))
+    }
+
+    /// Get the center of this conic
+    #[must_use]
+    pub fn center(self) -> Point {
+        Point(Expression {
+            expr: Node::call("Center", [self.0.expr]),
+            style: Point::bound(),
+        })
+    }
+
+    /// Get the midpoint of this conic, which GeoGebra defines as its center
+    #[must_use]
+    pub fn midpoint(self) -> Point {
+        Point(Expression {
+            expr: Node::call("Midpoint", [self.0.expr]),
+            style: Point::bound(),
+        })
+    }
+
+    /// Get the radius of this circle
+    #[must_use]
+    pub fn radius(self) -> Numeric {
+        Numeric(Expression::new(Node::call("Radius", [self.0.expr])))
+    }
+
+    /// Get the directrix of this parabola
+    #[must_use]
+    pub fn directrix(self) -> Line {
+        Line(Expression {
+            expr: Node::call("Directrix", [self.0.expr]),
+            style: Line::style(),
+        })
+    }
+
+    /// Get the major axis of this conic
+    #[must_use]
+    pub fn major_axis(self) -> Line {
+        Line(Expression {
+            expr: Node::call("MajorAxis", [self.0.expr]),
+            style: Line::style(),
+        })
+    }
+
+    /// Get the minor axis of this conic
+    #[must_use]
+    pub fn minor_axis(self) -> Line {
+        Line(Expression {
+            expr: Node::call("MinorAxis", [self.0.expr]),
+            style: Line::style(),
+        })
+    }
+}
+
+impl Object for Conic {}
+
+impl From<Expression> for Conic {
+    fn from(value: Expression) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Var<Conic>> for Conic {
+    fn from(value: Var<Conic>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<&Var<Conic>> for Conic {
+    fn from(value: &Var<Conic>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<Conic> for Expression {
+    fn from(value: Conic) -> Self {
+        value.0
+    }
+}
+
+impl Expr for Conic {
+    type Target = Self;
+
+    fn get_type() -> ElementType {
+        ElementType::Conic
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
+    }
+}
+
+/// Access for conic functions
+pub trait ConicAccess: Sized
+where
+    Conic: From<Self>,
+{
+    /// Get the conic's center
+    #[must_use]
+    fn center(self) -> Point {
+        Conic::from(self).center()
+    }
+
+    /// Get the conic's midpoint
+    #[must_use]
+    fn midpoint(self) -> Point {
+        Conic::from(self).midpoint()
+    }
+
+    /// Get the circle's radius
+    #[must_use]
+    fn radius(self) -> Numeric {
+        Conic::from(self).radius()
+    }
+
+    /// Get the parabola's directrix
+    #[must_use]
+    fn directrix(self) -> Line {
+        Conic::from(self).directrix()
+    }
+
+    /// Get the conic's major axis
+    #[must_use]
+    fn major_axis(self) -> Line {
+        Conic::from(self).major_axis()
+    }
+
+    /// Get the conic's minor axis
+    #[must_use]
+    fn minor_axis(self) -> Line {
+        Conic::from(self).minor_axis()
+    }
+}
+
+impl<T> ConicAccess for T where Conic: From<T> {}
+
+/// A ray (half-line)
+#[derive(Clone)]
+pub struct Ray(Expression);
+
+impl Ray {
+    /// Set the ray's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
+    }
+
+    /// Set the ray's style
+    pub fn set_style(&mut self, style: LineStyle) {
+        self.0.style.line_style = Some(style);
+    }
+
+    /// Make the ray dashed
+    pub fn set_dashed(&mut self) {
+        self.0.style.set_line_type(LineType::DashedLong);
+    }
+
+    /// Make the ray dotted
+    pub fn set_dotted(&mut self) {
+        self.0.style.set_line_type(LineType::Dotted);
+    }
+
+    /// Set the ray's opacity. The value is clamped to `[0, 1]` and ignored if it's not finite.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.0.style.set_opacity(opacity);
+    }
+
+    /// Wether to display this ray's label
+    pub fn set_display_label(&mut self, v: bool) {
+        self.0.style.display_label = v;
+    }
+
+    /// Set the size of this ray's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
+    }
+
+    /// Set whether this ray's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
+    }
+
+    /// Create a ray with an origin, going through a point
+    #[must_use]
+    pub fn new(origin: impl Into<Point>, through: impl Into<Point>) -> Self {
+        Self(Expression::new(Node::call(
+            "Ray",
+            [origin.into().0.expr, through.into().0.expr],
+        )))
+    }
+
+    /// Create a ray with an origin, going in the direction of a vector
+    #[must_use]
+    pub fn in_direction(origin: impl Into<Point>, direction: impl Into<Vector>) -> Self {
+        Self(Expression::new(Node::call(
+            "Ray",
+            [origin.into().0.expr, direction.into().0.expr],
+        )))
+    }
+
+    /// Mirror image of this ray across a line, styled like the original
+    #[must_use]
+    pub fn reflect(self, line: impl Into<Line>) -> Self {
+        Self(self.0.transform("Reflect", [line.into().0.expr]))
+    }
+
+    /// This ray rotated counterclockwise around a point, styled like the original
+    #[must_use]
+    pub fn rotate(self, angle: impl Into<Numeric>, center: impl Into<Point>) -> Self {
+        Self(
+            self.0
+                .transform("Rotate", [angle.into().0.expr, center.into().0.expr]),
+        )
+    }
+
+    /// This ray moved by a vector, styled like the original
+    #[must_use]
+    pub fn translate(self, v: impl Into<Vector>) -> Self {
+        Self(self.0.transform("Translate", [v.into().0.expr]))
+    }
+}
+
+impl Object for Ray {}
+
+impl From<Expression> for Ray {
+    fn from(value: Expression) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Var<Ray>> for Ray {
+    fn from(value: Var<Ray>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<&Var<Ray>> for Ray {
+    fn from(value: &Var<Ray>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<Ray> for Expression {
+    fn from(value: Ray) -> Self {
+        value.0
+    }
+}
+
+impl Expr for Ray {
+    type Target = Self;
+
+    fn get_type() -> ElementType {
+        ElementType::Ray
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
+    }
+}
+
+impl Addable for Ray {}
+
+/// A segment
+#[derive(Clone)]
+pub struct Segment(Expression);
+
+impl Segment {
+    /// Set the segment's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
+    }
+
+    /// Set the segment's style
+    pub fn set_style(&mut self, style: LineStyle) {
+        self.0.style.line_style = Some(style);
+    }
+
+    /// Make the segment dashed
+    pub fn set_dashed(&mut self) {
+        self.0.style.set_line_type(LineType::DashedLong);
+    }
+
+    /// Make the segment dotted
+    pub fn set_dotted(&mut self) {
+        self.0.style.set_line_type(LineType::Dotted);
+    }
+
+    /// Set the segment's opacity. The value is clamped to `[0, 1]` and ignored if it's not finite.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.0.style.set_opacity(opacity);
+    }
+
+    /// Wether to display this segment's label
+    pub fn set_display_label(&mut self, v: bool) {
+        self.0.style.display_label = v;
+    }
+
+    /// Set the size of this segment's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
+    }
+
+    /// Set whether this segment's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
+    }
+
+    /// Create a segment connecting two points
+    #[must_use]
+    pub fn new(a: impl Into<Point>, b: impl Into<Point>) -> Self {
+        Self(Expression::new(Node::call(
+            "Segment",
+            [a.into().0.expr, b.into().0.expr],
+        )))
+    }
+
+    /// Create a segment from a point to that point moved by a vector
+    #[must_use]
+    pub fn from_vector(origin: impl Into<Point>, v: impl Into<Vector>) -> Self {
+        let origin = origin.into().0.expr;
+
+        Self(Expression::new(Node::template(
+            "Segment({1}, ({1}) + ({0}))",
+            [v.into().0.expr, origin],
+        )))
+    }
+
+    /// Create a segment of a given length starting at a point, at an angle from the x axis
+    #[must_use]
+    pub fn with_length(
+        a: impl Into<Point>,
+        length: impl Into<Numeric>,
+        direction: impl Into<Numeric>,
+    ) -> Self {
+        let a = a.into().0.expr;
+
+        Self(Expression::new(Node::template(
+            "Rotate(Segment({2}, {0}), {1}, {2})",
+            [length.into().0.expr, direction.into().0.expr, a],
+        )))
+    }
+
+    /// Mirror image of this segment across a line, styled like the original
+    #[must_use]
+    pub fn reflect(self, line: impl Into<Line>) -> Self {
+        Self(self.0.transform("Reflect", [line.into().0.expr]))
+    }
+
+    /// This segment rotated counterclockwise around a point, styled like the original
+    #[must_use]
+    pub fn rotate(self, angle: impl Into<Numeric>, center: impl Into<Point>) -> Self {
+        Self(
+            self.0
+                .transform("Rotate", [angle.into().0.expr, center.into().0.expr]),
+        )
+    }
+
+    /// This segment moved by a vector, styled like the original
+    #[must_use]
+    pub fn translate(self, v: impl Into<Vector>) -> Self {
+        Self(self.0.transform("Translate", [v.into().0.expr]))
+    }
+
+    /// Midpoint of this segment
+    #[must_use]
+    pub fn midpoint(self) -> Point {
+        Point(Expression {
+            expr: Node::call("Midpoint", [self.0.expr]),
+            style: Point::bound(),
+        })
+    }
+
+    /// Vector from the segment's start to its end
+    #[must_use]
+    pub fn to_vector(self) -> Vector {
+        Vector(Expression {
+            expr: Node::call("Vector", [self.0.expr]),
+            style: Vector::style(),
+        })
+    }
+}
+
+impl Object for Segment {}
+
+impl From<Expression> for Segment {
+    fn from(value: Expression) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Var<Segment>> for Segment {
+    fn from(value: Var<Segment>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<&Var<Segment>> for Segment {
+    fn from(value: &Var<Segment>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<Segment> for Expression {
+    fn from(value: Segment) -> Self {
+        value.0
+    }
+}
+
+impl Expr for Segment {
+    type Target = Self;
+
+    fn get_type() -> ElementType {
+        ElementType::Segment
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
+    }
+}
+
+/// Trait with segment-related functions
+pub trait SegmentAccess: Sized
+where
+    Segment: From<Self>,
+{
+    /// Midpoint of this segment
+    #[must_use]
+    fn midpoint(self) -> Point {
+        Segment::from(self).midpoint()
+    }
+
+    /// Vector from the segment's start to its end
+    #[must_use]
+    fn to_vector(self) -> Vector {
+        Segment::from(self).to_vector()
+    }
+}
+
+impl<T> SegmentAccess for T where Segment: From<T> {}
+
+/// A vector
+#[derive(Clone)]
+pub struct Vector(Expression);
+
+impl Vector {
+    /// Set the vector's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
+    }
+
+    /// Set the vector's style
+    pub fn set_style(&mut self, style: LineStyle) {
+        self.0.style.line_style = Some(style);
+    }
+
+    /// Make the vector dashed
+    pub fn set_dashed(&mut self) {
+        self.0.style.set_line_type(LineType::DashedLong);
+    }
+
+    /// Make the vector dotted
+    pub fn set_dotted(&mut self) {
+        self.0.style.set_line_type(LineType::Dotted);
+    }
+
+    /// Set the vector's opacity. The value is clamped to `[0, 1]` and ignored if it's not finite.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.0.style.set_opacity(opacity);
+    }
+
+    /// Wether to display this vector's label
+    pub fn set_display_label(&mut self, v: bool) {
+        self.0.style.display_label = v;
+    }
+
+    /// Set the size of this vector's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
+    }
+
+    /// Set whether this vector's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
+    }
+
+    /// Default vector style.
     #[must_use]
-    fn free() -> Style {
+    fn style() -> Style {
         Style {
-            display_label: true,
-            line_style: None,
-            color: Some(ObjColorType {
-                r: 21,
-                g: 101,
-                b: 192,
-            }),
+            display_label: false,
+            line_style: Some(LineStyle::default()),
+            color: None,
+            font: None,
+            start_point: None,
+            latex: false,
         }
     }
 
-    /// Intersection of two lines
+    /// Create a vector from one point to another
     #[must_use]
-    pub fn intersect(k: impl Into<Line>, l: impl Into<Line>) -> Self {
+    pub fn new(from: impl Into<Point>, to: impl Into<Point>) -> Self {
         Self(Expression {
-            expr: Rc::new(format!(
-                "Intersect({}, {})",
-                k.into().0.expr,
-                l.into().0.expr
-            )),
-            style: Self::bound(),
+            expr: Node::call("Vector", [from.into().0.expr, to.into().0.expr]),
+            style: Self::style(),
         })
     }
 
-    /// Point on another geometric object
+    /// Position vector of a point
     #[must_use]
-    pub fn on(v: impl Object) -> Self {
+    pub fn position(point: impl Into<Point>) -> Self {
         Self(Expression {
-            expr: Rc::new(format!("Point({})", v.into().expr)),
-            style: Self::free(),
-        })
-    }
-
-    /// Get the x coordinate of this point
-    #[must_use]
-    pub fn x(self) -> Numeric {
-        Numeric(Expression {
-            expr: Rc::new(format!("x({})", self.0.expr)),
-            style: Style::default(),
+            expr: Node::call("Vector", [point.into().0.expr]),
+            style: Self::style(),
         })
     }
+}
 
-    /// Get the y coordinate of this point
-    #[must_use]
-    pub fn y(self) -> Numeric {
-        Numeric(Expression {
-            expr: Rc::new(format!("y({})", self.0.expr)),
-            style: Style::default(),
-        })
-    }
+impl Object for Vector {}
 
-    /// Convert this point to a complex number
-    #[must_use]
-    pub fn complex(self) -> Numeric {
-        Numeric(Expression::expr(format!("ToComplex({})", self.0.expr)))
+impl From<Expression> for Vector {
+    fn from(value: Expression) -> Self {
+        Self(value)
     }
 }
 
-impl<X: Into<Numeric>, Y: Into<Numeric>> From<(X, Y)> for Point {
-    fn from(value: (X, Y)) -> Self {
-        Self(Expression::from(value))
+impl From<Var<Vector>> for Vector {
+    fn from(value: Var<Vector>) -> Self {
+        Self(value.into())
     }
 }
 
-impl From<Var<Point>> for Point {
-    fn from(value: Var<Point>) -> Self {
-        Self(Expression::from(value))
+impl From<&Var<Vector>> for Vector {
+    fn from(value: &Var<Vector>) -> Self {
+        Self(value.into())
     }
 }
 
-impl From<&Var<Point>> for Point {
-    fn from(value: &Var<Point>) -> Self {
-        Self(Expression::from(value))
+impl From<Vector> for Expression {
+    fn from(value: Vector) -> Self {
+        value.0
     }
 }
 
-impl Expr for Point {
+impl Expr for Vector {
     type Target = Self;
 
     fn get_type() -> ElementType {
-        ElementType::Point
+        ElementType::Vector
     }
 
     fn var(expr: String) -> Var<Self::Target> {
@@ -351,51 +4148,116 @@ impl Expr for Point {
     }
 }
 
-impl Object for Point {}
+/// An angle, drawn as an arc
+#[derive(Clone)]
+pub struct Angle(Expression);
 
-impl<X: Into<Numeric>, Y: Into<Numeric>> Expr for (X, Y) {
-    type Target = Point;
+impl Angle {
+    /// Set the angle's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
+    }
 
-    fn get_type() -> ElementType {
-        ElementType::Point
+    /// Wether to display this angle's label
+    pub fn set_display_label(&mut self, v: bool) {
+        self.0.style.display_label = v;
     }
 
-    fn var(expr: String) -> Var<Self::Target> {
-        Var::new(expr)
+    /// Set the size of this angle's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
     }
-}
 
-/// Trait with point-related functions
-pub trait PointAccess: Sized
-where
-    Point: From<Self>,
-{
-    /// Get the x coordinate of this point
+    /// Set whether this angle's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
+    }
+
+    /// An angle defined by three points, with the vertex in the middle
     #[must_use]
-    fn x(self) -> Numeric {
-        Point::from(self).x()
+    pub fn new(a: impl Into<Point>, b: impl Into<Point>, c: impl Into<Point>) -> Self {
+        Self(Expression::new(Node::call(
+            "Angle",
+            [a.into().0.expr, b.into().0.expr, c.into().0.expr],
+        )))
     }
 
-    /// Get the y coordinate of this point
+    /// Angle between two lines
     #[must_use]
-    fn y(self) -> Numeric {
-        Point::from(self).x()
+    pub fn between_lines(k: impl Into<Line>, l: impl Into<Line>) -> Self {
+        Self(Expression::new(Node::call(
+            "Angle",
+            [k.into().0.expr, l.into().0.expr],
+        )))
     }
 
-    /// Convert to a complex number
+    /// Angle between two vectors
     #[must_use]
-    fn complex(self) -> Numeric {
-        Point::from(self).complex()
+    pub fn between_vectors(u: impl Into<Vector>, v: impl Into<Vector>) -> Self {
+        Self(Expression::new(Node::call(
+            "Angle",
+            [u.into().0.expr, v.into().0.expr],
+        )))
     }
 }
 
-impl<T> PointAccess for T where Point: From<T> {}
+impl Object for Angle {}
 
-impl Expr for f64 {
-    type Target = Numeric;
+impl From<Expression> for Angle {
+    fn from(value: Expression) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Var<Angle>> for Angle {
+    fn from(value: Var<Angle>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<&Var<Angle>> for Angle {
+    fn from(value: &Var<Angle>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<Angle> for Expression {
+    fn from(value: Angle) -> Self {
+        value.0
+    }
+}
+
+impl From<Angle> for Numeric {
+    fn from(value: Angle) -> Self {
+        Self(Expression {
+            expr: value.0.expr,
+            style: Style::default(),
+        })
+    }
+}
+
+impl From<Var<Angle>> for Numeric {
+    fn from(value: Var<Angle>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<&Var<Angle>> for Numeric {
+    fn from(value: &Var<Angle>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl Expr for Angle {
+    type Target = Self;
 
     fn get_type() -> ElementType {
-        ElementType::Numeric
+        ElementType::Angle
     }
 
     fn var(expr: String) -> Var<Self::Target> {
@@ -403,449 +4265,389 @@ impl Expr for f64 {
     }
 }
 
-/// A line element of the Geogebra construction
+/// A polygon
 #[derive(Clone)]
-pub struct Line(Expression);
+pub struct Polygon(Expression);
 
-impl Line {
-    /// Set the line's color
+impl Polygon {
+    /// Set the polygon's color
     pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
-        self.0.style.color = Some(ObjColorType { r, g, b });
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
     }
 
-    /// Set the line's style
+    /// Set the polygon's color, with the opacity of its filling. The opacity is clamped to `[0, 1]`
+    /// and the call is ignored if it's not finite.
+    pub fn set_color_alpha(&mut self, r: u8, g: u8, b: u8, alpha: f64) {
+        self.0.style.set_color_alpha(r, g, b, alpha);
+    }
+
+    /// Set the polygon's style
     pub fn set_style(&mut self, style: LineStyle) {
         self.0.style.line_style = Some(style);
     }
 
-    /// Wether to display this line's label
+    /// Make the polygon dashed
+    pub fn set_dashed(&mut self) {
+        self.0.style.set_line_type(LineType::DashedLong);
+    }
+
+    /// Make the polygon dotted
+    pub fn set_dotted(&mut self) {
+        self.0.style.set_line_type(LineType::Dotted);
+    }
+
+    /// Set the polygon's opacity. The value is clamped to `[0, 1]` and ignored if it's not finite.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.0.style.set_opacity(opacity);
+    }
+
+    /// Wether to display this polygon's label
     pub fn set_display_label(&mut self, v: bool) {
         self.0.style.display_label = v;
     }
 
-    /// Default line style.
+    /// Set the size of this polygon's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
+    }
+
+    /// Set whether this polygon's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
+    }
+
+    /// Default polygon style.
     #[must_use]
     fn style() -> Style {
         Style {
             display_label: false,
-            line_style: Some(LineStyle::default()),
+            line_style: None,
             color: None,
+            font: None,
+            start_point: None,
+            latex: false,
         }
     }
 
-    /// Make a line through two points.
+    /// Create a polygon from its vertices, in order
     #[must_use]
-    pub fn new(a: impl Into<Point>, b: impl Into<Point>) -> Self {
+    pub fn new<P: Into<Point>>(vertices: impl IntoIterator<Item = P>) -> Self {
         Self(Expression {
-            expr: Rc::new(format!("Line({}, {})", a.into().0.expr, b.into().0.expr)),
+            expr: Node::call(
+                "Polygon",
+                vertices.into_iter().map(|vertex| vertex.into().0.expr),
+            ),
             style: Self::style(),
         })
     }
 
-    /// Make a line from a point and a direction vector
+    /// Create a polygon from a list of its vertices, in order
     #[must_use]
-    pub fn point_vector(point: impl Into<Point>, vector: impl Into<Numeric>) -> Self {
+    pub fn from_list(points: impl Into<List<Point>>) -> Self {
         Self(Expression {
-            expr: Rc::new(format!(
-                "Line({}, {})",
-                point.into().0.expr,
-                vector.into().0.expr
-            )),
+            expr: Node::call("Polygon", [points.into().0.expr]),
             style: Self::style(),
         })
     }
 
-    /// Bisector of an angle
+    /// Mirror image of this polygon across a line, styled like the original
     #[must_use]
-    pub fn angle_bisector(a: impl Into<Point>, b: impl Into<Point>, c: impl Into<Point>) -> Self {
-        Self(Expression::expr(format!(
-            "AngleBisector({}, {}, {})",
-            a.into().0.expr,
-            b.into().0.expr,
-            c.into().0.expr
-        )))
+    pub fn reflect(self, line: impl Into<Line>) -> Self {
+        Self(self.0.transform("Reflect", [line.into().0.expr]))
     }
 
-    /// A line perpendicular to another, going through a point
+    /// This polygon rotated counterclockwise around a point, styled like the original
     #[must_use]
-    pub fn perpendicular(to: impl Into<Line>, through: impl Into<Point>) -> Self {
-        Self(Expression::expr(format!(
-            "PerpendicularLine({}, {})",
-            through.into().0.expr,
-            to.into().0.expr
-        )))
+    pub fn rotate(self, angle: impl Into<Numeric>, center: impl Into<Point>) -> Self {
+        Self(
+            self.0
+                .transform("Rotate", [angle.into().0.expr, center.into().0.expr]),
+        )
     }
 
-    /// A line parallel to another, going through a point
+    /// This polygon moved by a vector, styled like the original
     #[must_use]
-    pub fn parallel(to: impl Into<Line>, through: impl Into<Point>) -> Self {
-        Self(Expression::expr(format!(
-            "Line({}, {})",
-            through.into().0.expr,
-            to.into().0.expr
-        )))
+    pub fn translate(self, v: impl Into<Vector>) -> Self {
+        Self(self.0.transform("Translate", [v.into().0.expr]))
     }
 
-    /// Direction vector of this line
+    /// Area of this polygon
     #[must_use]
-    pub fn direction(self) -> Numeric {
-        Numeric(Expression::expr(format!("Direction({})", self.0.expr)))
+    pub fn area(self) -> Numeric {
+        Numeric(Expression::new(Node::call("Area", [self.0.expr])))
     }
-}
-
-impl From<Var<Line>> for Line {
-    fn from(value: Var<Line>) -> Self {
-        Self(Expression::from(value))
-    }
-}
-
-impl From<&Var<Line>> for Line {
-    fn from(value: &Var<Line>) -> Self {
-        Self(Expression::from(value))
-    }
-}
-
-impl From<Line> for Expression {
-    fn from(value: Line) -> Self {
-        value.0
-    }
-}
-
-impl Expr for Line {
-    type Target = Self;
-
-    fn get_type() -> ElementType {
-        ElementType::Line
-    }
-
-    fn var(expr: String) -> Var<Self::Target> {
-        Var::new(expr)
-    }
-}
-
-impl Object for Line {}
 
-/// Trait with line-related functions
-pub trait LineAccess: Sized
-where
-    Line: From<Self>,
-{
-    /// Direction vector of this line
+    /// Perimeter of this polygon
     #[must_use]
-    fn direction(self) -> Numeric {
-        Line::from(self).direction()
+    pub fn perimeter(self) -> Numeric {
+        Numeric(Expression::new(Node::call("Perimeter", [self.0.expr])))
     }
-}
-
-impl<T> LineAccess for T where Line: From<T> {}
-
-/// A list expression
-#[derive(Clone)]
-pub struct List<T>(Expression, PhantomData<T>);
 
-impl<T: Expr, It: IntoIterator<Item = T>> From<It> for List<T::Target>
-where
-    Expression: From<T>,
-{
-    fn from(value: It) -> Self {
-        let mut args = String::new();
-
-        for arg in value {
-            args += Expression::from(arg).expr.as_ref();
-            args += ", "
-        }
-
-        args.pop();
-        args.pop();
-
-        Self(
-            Expression {
-                expr: Rc::new(format!("{{{args}}}")),
-                style: Style::default(),
-            },
+    /// Edges of this polygon, each going from a vertex to the next one
+    #[must_use]
+    pub fn edges(self) -> List<Segment> {
+        let (i, v) = (INDEX_VARIABLE, LIST_VARIABLE);
+
+        // `Zip` over a one-item list binds the vertices, so the polygon is only written once.
+        List(
+            Expression::new(Node::template(
+                "Flatten(Zip(Sequence(Segment(Element({1}, {2}), Element({1}, Mod({2}, Length({1})) + 1)), {2}, 1, Length({1})), {1}, {{Vertex({0})}}))",
+                [self.0.expr, Node::text(v), Node::text(i)],
+            )),
             PhantomData,
         )
     }
-}
 
-impl<T> From<List<T>> for Expression {
-    fn from(value: List<T>) -> Self {
-        value.0
+    /// Area of this polygon, positive if the vertices go counterclockwise and negative otherwise
+    #[must_use]
+    pub fn signed_area(self) -> Numeric {
+        let (i, v) = (Node::text(INDEX_VARIABLE), Node::text(LIST_VARIABLE));
+        let next = Node::template(
+            "Element({0}, Mod({1}, Length({0})) + 1)",
+            [Rc::clone(&v), Rc::clone(&i)],
+        );
+        let current = Node::call("Element", [Rc::clone(&v), Rc::clone(&i)]);
+
+        // Bound like in `edges`, so the polygon is only written once.
+        Numeric(Expression::new(Node::template(
+            "Element(Zip(Sum(x({1}) y({2}) - x({2}) y({1}), {3}, 1, Length({4})), {4}, {{Vertex({0})}}), 1) / 2",
+            [self.0.expr, current, next, i, v],
+        )))
     }
 }
 
-impl<T> Expr for List<T> {
-    type Target = List<T>;
-
-    fn get_type() -> ElementType {
-        ElementType::List
-    }
+impl Object for Polygon {}
 
-    fn var(expr: String) -> Var<Self::Target> {
-        Var::new(expr)
+impl From<Expression> for Polygon {
+    fn from(value: Expression) -> Self {
+        Self(value)
     }
 }
 
-impl<T> From<&Var<List<T>>> for List<T> {
-    fn from(value: &Var<List<T>>) -> Self {
-        Self(value.into(), PhantomData)
+impl From<Var<Polygon>> for Polygon {
+    fn from(value: Var<Polygon>) -> Self {
+        Self(value.into())
     }
 }
 
-impl List<Point> {
-    /// Mean value of X coordinates of points.
-    #[must_use]
-    pub fn mean_x(self) -> Numeric {
-        Numeric(Expression {
-            expr: Rc::new(format!("MeanX({})", self.0.expr)),
-            style: Style::default(),
-        })
+impl From<&Var<Polygon>> for Polygon {
+    fn from(value: &Var<Polygon>) -> Self {
+        Self(value.into())
     }
+}
 
-    /// Mean value of Y coordinates of points.
-    #[must_use]
-    pub fn mean_y(self) -> Numeric {
-        Numeric(Expression {
-            expr: Rc::new(format!("MeanX({})", self.0.expr)),
-            style: Style::default(),
-        })
+impl From<Polygon> for Expression {
+    fn from(value: Polygon) -> Self {
+        value.0
     }
 }
 
-impl List<Numeric> {
-    /// Sum of these numbers
-    #[must_use]
-    pub fn sum(self) -> Numeric {
-        Numeric(Expression {
-            expr: Rc::new(format!("Sum(Append({}, 0 + 0i))", self.0.expr)),
-            style: Style::default(),
-        })
+impl Expr for Polygon {
+    type Target = Self;
+
+    fn get_type() -> ElementType {
+        ElementType::Polygon
     }
 
-    /// Product of these numbers
-    #[must_use]
-    pub fn product(self) -> Numeric {
-        Numeric(Expression {
-            expr: Rc::new(format!("Product(Append({}, 1 + 0i))", self.0.expr)),
-            style: Style::default(),
-        })
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
     }
 }
 
-/// A trait for accessing list functions through convertible types
-pub trait ListAccess<T>: Sized
+/// Trait with polygon-related functions
+pub trait PolygonAccess: Sized
 where
-    List<T>: From<Self>,
+    Polygon: From<Self>,
 {
-    /// Get the mean X coordinate
-    fn mean_x(self) -> Numeric
-    where
-        List<Point>: From<Self>,
-    {
-        List::from(self).mean_x()
+    /// Area of this polygon
+    #[must_use]
+    fn area(self) -> Numeric {
+        Polygon::from(self).area()
     }
 
-    /// Get the mean Y coordinate
-    fn mean_y(self) -> Numeric
-    where
-        List<Point>: From<Self>,
-    {
-        List::from(self).mean_y()
+    /// Perimeter of this polygon
+    #[must_use]
+    fn perimeter(self) -> Numeric {
+        Polygon::from(self).perimeter()
     }
 
-    /// Get the sum of numbers
-    fn sum(self) -> Numeric
-    where
-        List<Numeric>: From<Self>,
-    {
-        List::from(self).sum()
+    /// Area of this polygon, signed by orientation
+    #[must_use]
+    fn signed_area(self) -> Numeric {
+        Polygon::from(self).signed_area()
     }
 
-    /// Get the product of numbers
-    fn product(self) -> Numeric
-    where
-        List<Numeric>: From<Self>,
-    {
-        List::from(self).product()
+    /// Edges of this polygon
+    #[must_use]
+    fn edges(self) -> List<Segment> {
+        Polygon::from(self).edges()
     }
 }
 
-impl<T, V> ListAccess<T> for V where List<T>: From<V> {}
+impl<T> PolygonAccess for T where Polygon: From<T> {}
 
-/// A number value
+/// A polygon with exactly three vertices
 #[derive(Clone)]
-pub struct Numeric(Expression);
+pub struct Triangle(Polygon);
 
-impl Numeric {
-    /// Check if this numeric is a constant
-    #[must_use]
-    pub fn is_const(&self) -> bool {
-        self.0.expr.parse::<f64>().is_ok()
+impl Triangle {
+    /// Set the triangle's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.set_color(r, g, b);
     }
 
-    /// Distance between a point and an object
-    #[must_use]
-    pub fn distance<T: Object>(point: impl Into<Point>, object: T) -> Self {
-        Self(Expression {
-            expr: Rc::new(format!(
-                "Distance({}, {})",
-                point.into().0.expr,
-                object.into().expr
-            )),
-            style: Style::default(),
-        })
+    /// Set the triangle's color, with the opacity of its filling. The opacity is clamped to `[0, 1]`
+    /// and the call is ignored if it's not finite.
+    pub fn set_color_alpha(&mut self, r: u8, g: u8, b: u8, alpha: f64) {
+        self.0.set_color_alpha(r, g, b, alpha);
     }
 
-    /// A complex number
-    #[must_use]
-    pub fn complex(real: impl Into<Numeric>, imaginary: impl Into<Numeric>) -> Self {
-        Self(Expression {
-            expr: Rc::new(format!(
-                "({}) + ({})i",
-                real.into().0.expr,
-                imaginary.into().0.expr
-            )),
-            style: Style::default(),
-        })
+    /// Set the triangle's line style
+    pub fn set_style(&mut self, style: LineStyle) {
+        self.0.set_style(style);
     }
 
-    /// An angle defined by three points
-    #[must_use]
-    pub fn angle(a: impl Into<Point>, b: impl Into<Point>, c: impl Into<Point>) -> Self {
-        Self(Expression::expr(format!(
-            "Angle({}, {}, {})",
-            a.into().0.expr,
-            b.into().0.expr,
-            c.into().0.expr
-        )))
+    /// Draw the triangle's edges dashed
+    pub fn set_dashed(&mut self) {
+        self.0.set_dashed();
     }
 
-    /// Angle between two lines
-    #[must_use]
-    pub fn angle_lines(k: impl Into<Line>, l: impl Into<Line>) -> Self {
-        Self(Expression::expr(format!(
-            "Angle({}, {})",
-            k.into().0.expr,
-            l.into().0.expr
-        )))
+    /// Draw the triangle's edges dotted
+    pub fn set_dotted(&mut self) {
+        self.0.set_dotted();
     }
 
-    /// atan2 function
-    #[must_use]
-    pub fn atan2(y: impl Into<Numeric>, x: impl Into<Numeric>) -> Self {
-        Self(Expression::expr(format!(
-            "atan2({}, {})",
-            y.into().0.expr,
-            x.into().0.expr
-        )))
+    /// Set the triangle's opacity. The value is clamped to `[0, 1]` and ignored if it's not finite.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.0.set_opacity(opacity);
     }
 
-    /// Raise this number to a power.
-    #[must_use]
-    pub fn pow(self, exponent: impl Into<Numeric>) -> Self {
-        Self(Expression::expr(format!(
-            "({})^({})",
-            self.0.expr,
-            exponent.into().0.expr
-        )))
+    /// Wether to display this triangle's label
+    pub fn set_display_label(&mut self, v: bool) {
+        self.0.set_display_label(v);
     }
 
-    /// Get the real part of this number
-    #[must_use]
-    pub fn real(self) -> Self {
-        Self(Expression::expr(format!("real({})", self.0.expr)))
+    /// Set the size of this triangle's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.set_caption_size(size);
     }
 
-    /// Get the imaginary part of this number
-    #[must_use]
-    pub fn imaginary(self) -> Self {
-        Self(Expression::expr(format!("imaginary({})", self.0.expr)))
+    /// Set whether this triangle's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.set_caption_style(bold, italic);
     }
 
-    /// Natural logarithm (base e)
+    /// Create a triangle from its three vertices
     #[must_use]
-    pub fn ln(self) -> Self {
-        Self(Expression::expr(format!("ln({})", self.0.expr)))
+    pub fn new(a: impl Into<Point>, b: impl Into<Point>, c: impl Into<Point>) -> Self {
+        Self(Polygon::new([a.into(), b.into(), c.into()]))
     }
 
-    /// Exponential function (e^this)
+    /// Mirror image of this triangle across a line, styled like the original
     #[must_use]
-    pub fn exp(self) -> Self {
-        Self(Expression::expr(format!("exp({})", self.0.expr)))
+    pub fn reflect(self, line: impl Into<Line>) -> Self {
+        Self(self.0.reflect(line))
     }
 
-    /// Get the argument of a complex number.
+    /// This triangle rotated counterclockwise around a point, styled like the original
     #[must_use]
-    pub fn arg(self) -> Self {
-        Self(Expression::expr(format!("arg({})", self.0.expr)))
+    pub fn rotate(self, angle: impl Into<Numeric>, center: impl Into<Point>) -> Self {
+        Self(self.0.rotate(angle, center))
     }
 
-    /// Convert this to a point
+    /// This triangle moved by a vector, styled like the original
     #[must_use]
-    pub fn point(self) -> Point {
-        Point(Expression::expr(format!("ToPoint({})", self.0.expr)))
+    pub fn translate(self, v: impl Into<Vector>) -> Self {
+        Self(self.0.translate(v))
     }
 
-    /// Get the sine of this angle.
+    /// Area of this triangle
     #[must_use]
-    pub fn sin(self) -> Numeric {
-        Numeric(Expression::expr(format!("sin({})", self.0.expr)))
+    pub fn area(self) -> Numeric {
+        self.0.area()
     }
 
-    /// Get the cosine of this angle.
+    /// Perimeter of this triangle
     #[must_use]
-    pub fn cos(self) -> Numeric {
-        Numeric(Expression::expr(format!("cos({})", self.0.expr)))
+    pub fn perimeter(self) -> Numeric {
+        self.0.perimeter()
     }
 
-    /// Get the arcsine of this angle.
+    /// Radius of the circle going through all three vertices
     #[must_use]
-    pub fn asin(self) -> Numeric {
-        Numeric(Expression::expr(format!("asin({})", self.0.expr)))
+    pub fn circumradius(self) -> Numeric {
+        let t = self.0 .0.expr;
+        Numeric(Expression::new(Node::template(
+            "Radius(Circle(Vertex({0}, 1), Vertex({0}, 2), Vertex({0}, 3)))",
+            [t],
+        )))
     }
 
-    /// Get the arccosine of this angle.
+    /// Radius of the circle tangent to all three edges
     #[must_use]
-    pub fn acos(self) -> Numeric {
-        Numeric(Expression::expr(format!("acos({})", self.0.expr)))
+    pub fn inradius(self) -> Numeric {
+        let t = self.0 .0.expr;
+        Numeric(Expression::new(Node::template(
+            "2 Area({0}) / Perimeter({0})",
+            [t],
+        )))
     }
+}
 
-    /// Get the arctan of this angle.
-    #[must_use]
-    pub fn atan(self) -> Numeric {
-        Numeric(Expression::expr(format!("atan({})", self.0.expr)))
+impl Object for Triangle {}
+
+impl From<Expression> for Triangle {
+    fn from(value: Expression) -> Self {
+        Self(Polygon(value))
     }
+}
 
-    /// Normalize the value (abs of 1)
-    #[must_use]
-    pub fn normalize(self) -> Numeric {
-        Numeric(Expression::expr(format!("UnitVector({})", self.0.expr)))
+impl From<Var<Triangle>> for Triangle {
+    fn from(value: Var<Triangle>) -> Self {
+        Self(Polygon(value.into()))
     }
 }
 
-impl From<f64> for Numeric {
-    fn from(value: f64) -> Self {
-        Self(Expression::from(value))
+impl From<&Var<Triangle>> for Triangle {
+    fn from(value: &Var<Triangle>) -> Self {
+        Self(Polygon(value.into()))
     }
 }
 
-impl From<&Var<Numeric>> for Numeric {
-    fn from(value: &Var<Numeric>) -> Self {
-        Self(Expression::from(value))
+impl From<Triangle> for Expression {
+    fn from(value: Triangle) -> Self {
+        value.0 .0
     }
 }
 
-impl From<Numeric> for Expression {
-    fn from(value: Numeric) -> Self {
+impl From<Triangle> for Polygon {
+    fn from(value: Triangle) -> Self {
         value.0
     }
 }
 
-impl Expr for Numeric {
+impl From<Var<Triangle>> for Polygon {
+    fn from(value: Var<Triangle>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<&Var<Triangle>> for Polygon {
+    fn from(value: &Var<Triangle>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl Expr for Triangle {
     type Target = Self;
 
     fn get_type() -> ElementType {
-        ElementType::Numeric
+        ElementType::Polygon
     }
 
     fn var(expr: String) -> Var<Self::Target> {
@@ -853,349 +4655,431 @@ impl Expr for Numeric {
     }
 }
 
-impl<T: Into<Numeric>> Add<T> for Numeric {
-    type Output = Self;
+/// Trait with triangle-related functions
+pub trait TriangleAccess: Sized
+where
+    Triangle: From<Self>,
+{
+    /// Radius of the circumscribed circle
+    #[must_use]
+    fn circumradius(self) -> Numeric {
+        Triangle::from(self).circumradius()
+    }
 
-    fn add(mut self, rhs: T) -> Self::Output {
-        self += rhs;
-        self
+    /// Radius of the inscribed circle
+    #[must_use]
+    fn inradius(self) -> Numeric {
+        Triangle::from(self).inradius()
     }
 }
 
-impl<T: Into<Numeric>> AddAssign<T> for Numeric {
-    fn add_assign(&mut self, rhs: T) {
-        let expr = Expression {
-            expr: Rc::new(format!("({}) + ({})", self.0.expr, rhs.into().0.expr)),
-            style: Style::default(),
-        };
-        self.0 = expr;
+impl<T> TriangleAccess for T where Triangle: From<T> {}
+
+/// A region of the plane given by an inequality in `x` and `y`
+#[derive(Clone)]
+pub struct Inequality(Expression);
+
+impl Inequality {
+    /// Set the region's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
     }
-}
 
-impl<T: Into<Numeric>> Sub<T> for Numeric {
-    type Output = Self;
+    /// Set the region's color, with the opacity of its filling. The opacity is clamped to `[0, 1]`
+    /// and the call is ignored if it's not finite.
+    pub fn set_color_alpha(&mut self, r: u8, g: u8, b: u8, alpha: f64) {
+        self.0.style.set_color_alpha(r, g, b, alpha);
+    }
 
-    fn sub(mut self, rhs: T) -> Self::Output {
-        self -= rhs;
-        self
+    /// Wether to display this region's label
+    pub fn set_display_label(&mut self, v: bool) {
+        self.0.style.display_label = v;
     }
-}
 
-impl<T: Into<Numeric>> SubAssign<T> for Numeric {
-    fn sub_assign(&mut self, rhs: T) {
-        let expr = Expression {
-            expr: Rc::new(format!("({}) - ({})", self.0.expr, rhs.into().0.expr)),
-            style: Style::default(),
-        };
-        self.0 = expr;
+    /// Set the size of this region's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
+    }
+
+    /// Set whether this region's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
+    }
+
+    /// Default inequality style.
+    #[must_use]
+    fn style() -> Style {
+        Style {
+            display_label: false,
+            line_style: None,
+            color: None,
+            font: None,
+            start_point: None,
+            latex: false,
+        }
     }
 }
 
-impl<T: Into<Numeric>> Mul<T> for Numeric {
-    type Output = Self;
+impl Object for Inequality {}
 
-    fn mul(mut self, rhs: T) -> Self::Output {
-        self *= rhs;
-        self
+impl From<Expression> for Inequality {
+    fn from(value: Expression) -> Self {
+        Self(value)
     }
 }
 
-impl<T: Into<Numeric>> MulAssign<T> for Numeric {
-    fn mul_assign(&mut self, rhs: T) {
-        let expr = Expression {
-            expr: Rc::new(format!("({}) * ({})", self.0.expr, rhs.into().0.expr)),
-            style: Style::default(),
-        };
-        self.0 = expr;
+impl From<Var<Inequality>> for Inequality {
+    fn from(value: Var<Inequality>) -> Self {
+        Self(value.into())
     }
 }
 
-impl<T: Into<Numeric>> Div<T> for Numeric {
-    type Output = Self;
+impl From<&Var<Inequality>> for Inequality {
+    fn from(value: &Var<Inequality>) -> Self {
+        Self(value.into())
+    }
+}
 
-    fn div(mut self, rhs: T) -> Self::Output {
-        self /= rhs;
-        self
+impl From<Inequality> for Expression {
+    fn from(value: Inequality) -> Self {
+        value.0
     }
 }
 
-impl<T: Into<Numeric>> DivAssign<T> for Numeric {
-    fn div_assign(&mut self, rhs: T) {
-        let expr = Expression {
-            expr: Rc::new(format!("({}) / ({})", self.0.expr, rhs.into().0.expr)),
-            style: Style::default(),
-        };
-        self.0 = expr;
+impl Expr for Inequality {
+    type Target = Self;
+
+    fn get_type() -> ElementType {
+        ElementType::FunctionNVar
+    }
+
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
     }
 }
 
-impl Neg for Numeric {
-    type Output = Self;
+/// A real function, given by an expression in `x` (and `y` for functions of two variables)
+#[derive(Clone)]
+pub struct Function(Expression);
 
-    fn neg(self) -> Self::Output {
+impl Function {
+    /// Set the function's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
+    }
+
+    /// Set the function's line style
+    pub fn set_style(&mut self, style: LineStyle) {
+        self.0.style.line_style = Some(style);
+    }
+
+    /// Make the function's graph dashed
+    pub fn set_dashed(&mut self) {
+        self.0.style.set_line_type(LineType::DashedLong);
+    }
+
+    /// Make the function's graph dotted
+    pub fn set_dotted(&mut self) {
+        self.0.style.set_line_type(LineType::Dotted);
+    }
+
+    /// Set the function's opacity. The value is clamped to `[0, 1]` and ignored if it's not finite.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.0.style.set_opacity(opacity);
+    }
+
+    /// Wether to display this function's label
+    pub fn set_display_label(&mut self, v: bool) {
+        self.0.style.display_label = v;
+    }
+
+    /// Set the size of this function's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
+    }
+
+    /// Set whether this function's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
+    }
+
+    /// Default function style.
+    #[must_use]
+    fn style() -> Style {
+        Style {
+            display_label: false,
+            line_style: Some(LineStyle::default()),
+            color: None,
+            font: None,
+            start_point: None,
+            latex: false,
+        }
+    }
+
+    /// Create a function from its formula, using [`Numeric::var_x`] and [`Numeric::var_y`]
+    #[must_use]
+    pub fn new(body: impl Into<Numeric>) -> Self {
         Self(Expression {
-            expr: Rc::new(format!("-({})", self.0.expr)),
-            style: Style::default(),
+            expr: body.into().0.expr,
+            style: Self::style(),
         })
     }
 }
 
-impl<T: Into<Numeric>> Rem<T> for Numeric {
-    type Output = Numeric;
+impl Object for Function {}
 
-    fn rem(mut self, rhs: T) -> Self::Output {
-        self %= rhs;
-        self
+impl From<Expression> for Function {
+    fn from(value: Expression) -> Self {
+        Self(value)
     }
 }
 
-impl<T: Into<Numeric>> RemAssign<T> for Numeric {
-    fn rem_assign(&mut self, rhs: T) {
-        let expr = Expression::expr(format!("Mod({}, {})", self.0.expr, rhs.into().0.expr));
-        self.0 = expr;
+impl From<Var<Function>> for Function {
+    fn from(value: Var<Function>) -> Self {
+        Self(value.into())
     }
 }
 
-impl From<&Self> for Numeric {
-    fn from(value: &Self) -> Self {
-        Self(value.0.clone())
+impl From<&Var<Function>> for Function {
+    fn from(value: &Var<Function>) -> Self {
+        Self(value.into())
     }
 }
 
-impl<T: Copy + Into<Numeric>> From<&T> for Numeric {
-    fn from(value: &T) -> Self {
-        (*value).into()
+impl From<Function> for Expression {
+    fn from(value: Function) -> Self {
+        value.0
     }
 }
 
-impl<T> PartialEq<T> for Numeric
-where
-    Numeric: for<'a> From<&'a T>,
-{
-    fn eq(&self, other: &T) -> bool {
-        let other = Self::from(other);
-        if self.0.expr == other.0.expr {
-            return true;
-        }
-
-        if let Ok(v) = self.0.expr.parse::<f64>() {
-            if let Ok(u) = other.0.expr.parse::<f64>() {
-                return v.partial_cmp(&u).is_some_and(|v| v.is_eq());
-            }
-        }
-
-        false
-    }
-}
+impl Expr for Function {
+    type Target = Self;
 
-impl Zero for Numeric {
-    fn zero() -> Self {
-        Self(Expression::expr("0"))
+    fn get_type() -> ElementType {
+        ElementType::Function
     }
 
-    /// WARNING: This is not necessarily always precise
-    fn is_zero(&self) -> bool {
-        self.0.expr.as_str() == "0" || self.0.expr.as_str() == "0.0"
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
     }
 }
 
-impl One for Numeric {
-    fn one() -> Self {
-        Self(Expression::expr("1"))
-    }
+/// A parametric curve
+#[derive(Clone)]
+pub struct Curve(Expression);
 
-    /// WARNING: This is not necessarily always precise
-    fn is_one(&self) -> bool {
-        self.0.expr.as_str() == "1" || self.0.expr.as_str() == "1.0"
+impl Curve {
+    /// Set the curve's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
     }
-}
 
-impl Bounded for Numeric {
-    fn min_value() -> Self {
-        Self::zero()
+    /// Set the curve's line style
+    pub fn set_style(&mut self, style: LineStyle) {
+        self.0.style.line_style = Some(style);
     }
 
-    fn max_value() -> Self {
-        Self(Expression::expr(format!("{} + {}i", f64::MAX, f64::MAX)))
+    /// Make the curve dashed
+    pub fn set_dashed(&mut self) {
+        self.0.style.set_line_type(LineType::DashedLong);
     }
-}
-
-impl Num for Numeric {
-    type FromStrRadixErr = &'static str;
 
-    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
-        if radix != 10 {
-            return Err("Only radix of 10 is supported.");
-        }
-
-        Ok(Self(Expression::expr(str)))
+    /// Make the curve dotted
+    pub fn set_dotted(&mut self) {
+        self.0.style.set_line_type(LineType::Dotted);
     }
-}
 
-/// Trait for accessing numeric functions
-pub trait NumericAccess: Sized
-where
-    Numeric: From<Self>,
-{
-    /// Raise the number to a power
-    #[must_use]
-    fn pow(self, exponent: impl Into<Numeric>) -> Numeric {
-        Numeric::from(self).pow(exponent)
+    /// Set the curve's opacity. The value is clamped to `[0, 1]` and ignored if it's not finite.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.0.style.set_opacity(opacity);
     }
 
-    /// Get the real part of this number
-    #[must_use]
-    fn real(self) -> Numeric {
-        Numeric::from(self).real()
+    /// Wether to display this curve's label
+    pub fn set_display_label(&mut self, v: bool) {
+        self.0.style.display_label = v;
     }
 
-    /// Get the imaginary part of this number
-    #[must_use]
-    fn imaginary(self) -> Numeric {
-        Numeric::from(self).imaginary()
+    /// Set the size of this curve's label, relative to the default size
+    pub fn set_caption_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
     }
 
-    /// Get this complex number's argument
-    #[must_use]
-    fn arg(self) -> Numeric {
-        Numeric::from(self).arg()
+    /// Set whether this curve's label is bold and/or italic
+    pub fn set_caption_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
     }
 
-    /// Convert this number to a point
+    /// Default curve style.
     #[must_use]
-    fn point(self) -> Point {
-        Numeric::from(self).point()
+    fn style() -> Style {
+        Style {
+            display_label: false,
+            line_style: Some(LineStyle::default()),
+            color: None,
+            font: None,
+            start_point: None,
+            latex: false,
+        }
     }
 
-    /// Natural logarithm (base e)
+    /// A smooth curve going through a list of points, in order
     #[must_use]
-    fn ln(self) -> Numeric {
-        Numeric::from(self).ln()
+    pub fn spline(points: impl Into<List<Point>>) -> Self {
+        Self(Expression {
+            expr: Node::call("Spline", [points.into().0.expr]),
+            style: Self::style(),
+        })
     }
+}
 
-    /// Exponential function (e^this)
-    #[must_use]
-    fn exp(self) -> Numeric {
-        Numeric::from(self).exp()
-    }
+impl Object for Curve {}
 
-    /// Get the sine of this angle.
-    #[must_use]
-    fn sin(self) -> Numeric {
-        Numeric::from(self).sin()
+impl From<Expression> for Curve {
+    fn from(value: Expression) -> Self {
+        Self(value)
     }
+}
 
-    /// Get the cosine of this angle.
-    #[must_use]
-    fn cos(self) -> Numeric {
-        Numeric::from(self).cos()
+impl From<Var<Curve>> for Curve {
+    fn from(value: Var<Curve>) -> Self {
+        Self(value.into())
     }
+}
 
-    /// Get the arcsine of this angle.
-    #[must_use]
-    fn asin(self) -> Numeric {
-        Numeric::from(self).asin()
+impl From<&Var<Curve>> for Curve {
+    fn from(value: &Var<Curve>) -> Self {
+        Self(value.into())
     }
+}
 
-    /// Get the arccosine of this angle.
-    #[must_use]
-    fn acos(self) -> Numeric {
-        Numeric::from(self).acos()
+impl From<Curve> for Expression {
+    fn from(value: Curve) -> Self {
+        value.0
     }
+}
 
-    /// Get the arctan of this angle.
-    #[must_use]
-    fn atan(self) -> Numeric {
-        Numeric::from(self).atan()
+impl Expr for Curve {
+    type Target = Self;
+
+    fn get_type() -> ElementType {
+        ElementType::CurveCartesian
     }
 
-    /// Normalize this value (abs of 1)
-    #[must_use]
-    fn normalize(self) -> Numeric {
-        Numeric::from(self).normalize()
+    fn var(expr: String) -> Var<Self::Target> {
+        Var::new(expr)
     }
 }
 
-impl<T> NumericAccess for T where Numeric: From<Self> {}
-
-/// A circle in the construction
+/// A curve defined by a polynomial equation in `x` and `y`
 #[derive(Clone)]
-pub struct Conic(Expression);
+pub struct ImplicitCurve(Expression);
 
-impl Conic {
-    /// Set the line's color
+impl ImplicitCurve {
+    /// Set the curve's color
     pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
-        self.0.style.color = Some(ObjColorType { r, g, b });
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
     }
 
-    /// Set the line's style
+    /// Set the curve's line style
     pub fn set_style(&mut self, style: LineStyle) {
         self.0.style.line_style = Some(style);
     }
 
-    /// Wether to display this line's label
+    /// Make the curve dashed
+    pub fn set_dashed(&mut self) {
+        self.0.style.set_line_type(LineType::DashedLong);
+    }
+
+    /// Make the curve dotted
+    pub fn set_dotted(&mut self) {
+        self.0.style.set_line_type(LineType::Dotted);
+    }
+
+    /// Set the curve's opacity. The value is clamped to `[0, 1]` and ignored if it's not finite.
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.0.style.set_opacity(opacity);
+    }
+
+    /// Wether to display this curve's label
     pub fn set_display_label(&mut self, v: bool) {
         self.0.style.display_label = v;
     }
 
-    /// Default style for a conic
+    /// Default implicit curve style.
     #[must_use]
     fn style() -> Style {
         Style {
             display_label: false,
-            line_style: None,
+            line_style: Some(LineStyle::default()),
             color: None,
+            font: None,
+            start_point: None,
+            latex: false,
         }
     }
 
-    /// Create a new circle with a center and a radius
+    /// Curve of best fit through a list of points, of the given degree.
+    /// Degree 2 gives a conic-shaped curve, though GeoGebra doesn't treat it as a conic.
     #[must_use]
-    pub fn circle(center: impl Into<Point>, radius: impl Into<Numeric>) -> Self {
+    pub fn fit(points: impl Into<List<Point>>, degree: u32) -> Self {
         Self(Expression {
-            expr: Rc::new(format!(
-                "Circle({}, abs({}))",
-                center.into().0.expr,
-                radius.into().0.expr
-            )),
+            expr: Node::call("FitImplicit", [points.into().0.expr, Node::text(degree)]),
             style: Self::style(),
         })
     }
+}
 
-    /// Get the center of this conic
-    #[must_use]
-    pub fn center(self) -> Point {
-        Point(Expression {
-            expr: Rc::new(format!("Center({})", self.0.expr)),
-            style: Point::bound(),
-        })
+impl Object for ImplicitCurve {}
+
+impl From<Expression> for ImplicitCurve {
+    fn from(value: Expression) -> Self {
+        Self(value)
     }
 }
 
-impl Object for Conic {}
-
-impl From<Var<Conic>> for Conic {
-    fn from(value: Var<Conic>) -> Self {
+impl From<Var<ImplicitCurve>> for ImplicitCurve {
+    fn from(value: Var<ImplicitCurve>) -> Self {
         Self(value.into())
     }
 }
 
-impl From<&Var<Conic>> for Conic {
-    fn from(value: &Var<Conic>) -> Self {
+impl From<&Var<ImplicitCurve>> for ImplicitCurve {
+    fn from(value: &Var<ImplicitCurve>) -> Self {
         Self(value.into())
     }
 }
 
-impl From<Conic> for Expression {
-    fn from(value: Conic) -> Self {
+impl From<ImplicitCurve> for Expression {
+    fn from(value: ImplicitCurve) -> Self {
         value.0
     }
 }
 
-impl Expr for Conic {
+impl Expr for ImplicitCurve {
     type Target = Self;
 
     fn get_type() -> ElementType {
-        ElementType::Conic
+        ElementType::ImplicitPoly
     }
 
     fn var(expr: String) -> Var<Self::Target> {
@@ -1203,76 +5087,86 @@ impl Expr for Conic {
     }
 }
 
-/// Access for conic functions
-pub trait ConicAccess: Sized
-where
-    Conic: From<Self>,
-{
-    /// Get the conic's center
-    #[must_use]
-    fn center(self) -> Point {
-        Conic::from(self).center()
-    }
-}
-
-impl<T> ConicAccess for T where Conic: From<T> {}
-
-/// A ray (half-line)
+/// A set of points drawn as a whole, like a slope field
 #[derive(Clone)]
-pub struct Ray(Expression);
+pub struct Locus(Expression);
 
-impl Ray {
-    /// Set the line's color
+impl Locus {
+    /// Set the locus' color
     pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
-        self.0.style.color = Some(ObjColorType { r, g, b });
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
     }
 
-    /// Set the line's style
+    /// Set the locus' line style
     pub fn set_style(&mut self, style: LineStyle) {
         self.0.style.line_style = Some(style);
     }
 
-    /// Wether to display this line's label
+    /// Wether to display this locus' label
     pub fn set_display_label(&mut self, v: bool) {
         self.0.style.display_label = v;
     }
 
-    /// Create a ray with an origin, going through a point
+    /// Default locus style.
     #[must_use]
-    pub fn new(origin: impl Into<Point>, through: impl Into<Point>) -> Self {
-        Self(Expression::expr(format!(
-            "Ray({}, {})",
-            origin.into().0.expr,
-            through.into().0.expr
-        )))
+    fn style() -> Style {
+        Style {
+            display_label: false,
+            line_style: None,
+            color: None,
+            font: None,
+            start_point: None,
+            latex: false,
+        }
+    }
+
+    /// The slope field of a differential equation `y' = f(x, y)`,
+    /// with `density` segments along each axis
+    #[must_use]
+    pub fn slope_field(f: impl Into<Function>, density: u32) -> Self {
+        Self(Expression {
+            expr: Node::call("SlopeField", [f.into().0.expr, Node::text(density)]),
+            style: Self::style(),
+        })
     }
 }
 
-impl Object for Ray {}
+impl Object for Locus {}
 
-impl From<Var<Ray>> for Ray {
-    fn from(value: Var<Ray>) -> Self {
+impl From<Expression> for Locus {
+    fn from(value: Expression) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Var<Locus>> for Locus {
+    fn from(value: Var<Locus>) -> Self {
         Self(value.into())
     }
 }
 
-impl From<&Var<Ray>> for Ray {
-    fn from(value: &Var<Ray>) -> Self {
+impl From<&Var<Locus>> for Locus {
+    fn from(value: &Var<Locus>) -> Self {
         Self(value.into())
     }
 }
 
-impl From<Ray> for Expression {
-    fn from(value: Ray) -> Self {
+impl From<Locus> for Expression {
+    fn from(value: Locus) -> Self {
         value.0
     }
 }
 
-impl Expr for Ray {
+impl Expr for Locus {
     type Target = Self;
 
     fn get_type() -> ElementType {
-        ElementType::Ray
+        ElementType::Locus
     }
 
     fn var(expr: String) -> Var<Self::Target> {
@@ -1280,64 +5174,117 @@ impl Expr for Ray {
     }
 }
 
-impl Addable for Ray {}
-
-/// A segment
+/// A text shown in the graphics view
 #[derive(Clone)]
-pub struct Segment(Expression);
+pub struct Text(Expression);
+
+impl Text {
+    /// Set the text's color
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.0.style.color = Some(ObjColorType {
+            r,
+            g,
+            b,
+            alpha: None,
+        });
+    }
+
+    /// Set the size of the text, relative to the default size
+    pub fn set_size(&mut self, size: i8) {
+        self.0.style.set_caption_size(size);
+    }
+
+    /// Set whether the text is bold and/or italic
+    pub fn set_font_style(&mut self, bold: bool, italic: bool) {
+        self.0.style.set_caption_style(bold, italic);
+    }
+
+    /// Default text style.
+    #[must_use]
+    fn style() -> Style {
+        Style {
+            display_label: false,
+            line_style: None,
+            color: None,
+            font: None,
+            start_point: None,
+            latex: false,
+        }
+    }
 
-impl Segment {
-    /// Set the line's color
-    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
-        self.0.style.color = Some(ObjColorType { r, g, b });
+    /// Create a static text
+    #[must_use]
+    pub fn new(text: impl ToString) -> Self {
+        // GeoGebra strings can't escape quotes, so they're spliced in as characters.
+        let text = text
+            .to_string()
+            .replace('"', "\" + UnicodeToLetter(34) + \"");
+
+        Self(Expression {
+            expr: Node::text(format!("\"{text}\"")),
+            style: Self::style(),
+        })
     }
 
-    /// Set the line's style
-    pub fn set_style(&mut self, style: LineStyle) {
-        self.0.style.line_style = Some(style);
+    /// Create a LaTeX formula
+    #[must_use]
+    pub fn formula(latex: &str) -> Self {
+        let mut text = Self::new(latex);
+        text.0.style.latex = true;
+        text
     }
 
-    /// Wether to display this line's label
-    pub fn set_display_label(&mut self, v: bool) {
-        self.0.style.display_label = v;
+    /// Text showing the live ratio of two numbers, like `2 : 3`
+    #[must_use]
+    pub fn ratio(a: impl Into<Numeric>, b: impl Into<Numeric>) -> Self {
+        Self(Expression {
+            expr: Node::template(
+                "Text(real({0})) + \" : \" + Text(real({1}))",
+                [a.into().0.expr, b.into().0.expr],
+            ),
+            style: Self::style(),
+        })
     }
 
-    /// Create a segment connecting two points
+    /// Anchor the text's corner to a point, so that it follows the point around
     #[must_use]
-    pub fn new(a: impl Into<Point>, b: impl Into<Point>) -> Self {
-        Self(Expression::expr(format!(
-            "Segment({}, {})",
-            a.into().0.expr,
-            b.into().0.expr
-        )))
+    pub fn anchor(mut self, point: impl Into<Point>) -> Self {
+        self.0.style.start_point = Some(point.into().0.expr);
+        self
     }
 }
 
-impl Object for Segment {}
+impl Object for Text {}
 
-impl From<Var<Segment>> for Segment {
-    fn from(value: Var<Segment>) -> Self {
+impl From<Expression> for Text {
+    fn from(value: Expression) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Var<Text>> for Text {
+    fn from(value: Var<Text>) -> Self {
         Self(value.into())
     }
 }
 
-impl From<&Var<Segment>> for Segment {
-    fn from(value: &Var<Segment>) -> Self {
+impl From<&Var<Text>> for Text {
+    fn from(value: &Var<Text>) -> Self {
         Self(value.into())
     }
 }
 
-impl From<Segment> for Expression {
-    fn from(value: Segment) -> Self {
+impl From<Text> for Expression {
+    fn from(value: Text) -> Self {
         value.0
     }
 }
 
-impl Expr for Segment {
+impl Expr for Text {
     type Target = Self;
 
     fn get_type() -> ElementType {
-        ElementType::Segment
+        ElementType::Text
     }
 
     fn var(expr: String) -> Var<Self::Target> {
@@ -1356,6 +5303,28 @@ impl Addable for Conic {}
 
 impl Addable for Segment {}
 
+impl Addable for Vector {}
+
+impl Addable for Angle {}
+
+impl Addable for Polygon {}
+
+impl Addable for Triangle {}
+
+impl Addable for Inequality {}
+
+impl Addable for Function {}
+
+impl Addable for Curve {}
+
+impl Addable for Locus {}
+
+impl Addable for ImplicitCurve {}
+
+impl Addable for Text {}
+
+impl<T> Addable for List<T> {}
+
 impl Geogebra {
     /// Create an object defined by an expression.
     pub fn add<T: Expr>(&mut self, expr: T, caption: impl ToString) -> Var<T::Target>
@@ -1363,94 +5332,999 @@ impl Geogebra {
         T::Target: Addable,
     {
         let label = self.next_label();
-        let expr = expr.into();
+        self.add_labeled(expr, label, caption)
+    }
+
+    /// Create a list object from its items.
+    pub fn add_list<T: Expr>(
+        &mut self,
+        items: impl IntoIterator<Item = T>,
+        caption: impl ToString,
+    ) -> Var<List<T::Target>>
+    where
+        Expression: From<T>,
+    {
+        self.add(List::from(items), caption)
+    }
+
+    /// Create an object defined by an expression with a user-given label.
+    ///
+    /// # Errors
+    /// Returns an error if the label is malformed or already taken.
+    pub fn add_named<T: Expr>(
+        &mut self,
+        expr: T,
+        label: impl ToString,
+        caption: impl ToString,
+    ) -> Result<Var<T::Target>, LabelError>
+    where
+        T::Target: Addable,
+    {
+        let label = label.to_string();
+        validate_label(&label)?;
+
+        if self.is_label_taken(&label) {
+            return Err(LabelError::Taken(label));
+        }
+
+        Ok(self.add_labeled(expr, label, caption))
+    }
+
+    /// Add a command with several labeled outputs of the same type,
+    /// like `Intersect` of two circles giving both points.
+    ///
+    /// # Errors
+    /// Returns an error if an output label is malformed or already taken.
+    pub fn add_multi_output<T: Expr>(
+        &mut self,
+        name: &str,
+        inputs: impl IntoIterator<Item = impl Into<Expression>>,
+        output_labels: &[&str],
+    ) -> Result<Vec<Var<T::Target>>, LabelError>
+    where
+        T::Target: Addable,
+    {
+        for (i, label) in output_labels.iter().enumerate() {
+            validate_label(label)?;
+
+            if self.is_label_taken(label) || output_labels[..i].contains(label) {
+                return Err(LabelError::Taken(label.to_string()));
+            }
+        }
+
+        let labels = self.labels();
+        let input = inputs
+            .into_iter()
+            .map(|input| dedup_expression(&input.into().expr.to_string(), &labels.expressions))
+            .collect::<Vec<_>>();
+
+        labels
+            .defined
+            .extend(output_labels.iter().map(ToString::to_string));
 
         self.data
             .construction
             .items
-            .push(ConstructionItem::Expression(raw::Expression {
-                type_: T::get_type(),
-                label: label.clone(),
-                exp: expr.expr.as_ref().clone(),
+            .push(ConstructionItem::Command(raw::Command {
+                name: name.to_string(),
+                input: input.into(),
+                output: output_labels
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .into(),
             }));
 
+        Ok(output_labels
+            .iter()
+            .map(|label| {
+                self.data
+                    .construction
+                    .items
+                    .push(ConstructionItem::Element(Box::new(Element {
+                        type_: T::get_type(),
+                        label: label.to_string(),
+                        ..Style::default().to_element()
+                    })));
+
+                T::var(label.to_string())
+            })
+            .collect())
+    }
+
+    /// Create an object defined by an expression, choosing which of its parts are shown.
+    pub fn add_with_show<T: Expr>(
+        &mut self,
+        expr: T,
+        caption: impl ToString,
+        show: Show,
+    ) -> Var<T::Target>
+    where
+        T::Target: Addable,
+    {
+        let var = self.add(expr, caption);
+
+        if let Some(element) = self.element_mut(&var.0) {
+            element.show = show;
+        }
+
+        var
+    }
+
+    /// Create an object defined by an expression and style it after the fact.
+    pub fn add_styled<T: Expr>(
+        &mut self,
+        expr: T,
+        caption: impl ToString,
+    ) -> StyledVar<'_, T::Target>
+    where
+        T::Target: Addable,
+    {
+        let var = self.add(expr, caption);
+
+        StyledVar { ggb: self, var }
+    }
+
+    /// Add an object with the given label.
+    fn add_labeled<T: Expr>(
+        &mut self,
+        expr: T,
+        label: String,
+        caption: impl ToString,
+    ) -> Var<T::Target> {
+        let expr = expr.into();
+
+        self.push_expression(T::get_type(), &label, &expr.expr.to_string());
+
         self.data
             .construction
             .items
-            .push(ConstructionItem::Element(Element {
+            .push(ConstructionItem::Element(Box::new(Element {
                 type_: T::get_type(),
                 label: label.clone(),
                 caption: Some(caption.to_string().into()),
                 ..expr.style.to_element()
-            }));
+            })));
 
         T::var(label)
     }
 
     /// Add a point with a position hint.
+    ///
+    /// # Errors
+    /// Returns an error if the hint is not finite.
     pub fn add_point(
         &mut self,
         point: impl Into<Point>,
         caption: impl ToString,
         (x, y): (f64, f64),
-    ) -> Var<Point> {
+    ) -> Result<Var<Point>, NonFiniteCoords> {
+        let coords = Coords::try_xy(x, y)?;
         let label = self.next_label();
         let point = point.into();
 
+        self.push_expression(ElementType::Point, &label, &point.0.expr.to_string());
+
         self.data
             .construction
             .items
-            .push(ConstructionItem::Expression(raw::Expression {
+            .push(ConstructionItem::Element(Box::new(Element {
                 type_: ElementType::Point,
                 label: label.clone(),
-                exp: point.0.expr.as_ref().clone(),
-            }));
+                caption: Some(caption.to_string().into()),
+                coords: Some(coords),
+                ..point.0.style.to_element()
+            })));
+
+        Ok(Point::var(label))
+    }
+
+    /// Add a text with its corner at the given position.
+    /// The position replaces an anchor set with [`Text::anchor`].
+    ///
+    /// # Errors
+    /// Returns an error if the position is not finite.
+    pub fn add_text(
+        &mut self,
+        text: impl Into<Text>,
+        (x, y): (f64, f64),
+    ) -> Result<Var<Text>, NonFiniteCoords> {
+        let coords = Coords::try_xy(x, y)?;
+        let var = self.add(text.into(), "");
+
+        if let Some(element) = self.element_mut(&var.0) {
+            element.start_point = Some(coords.into());
+        }
+
+        Ok(var)
+    }
+
+    /// Add a free point at the given position, without a defining expression.
+    /// Unlike [`Geogebra::add_point`], it can be dragged around.
+    ///
+    /// # Errors
+    /// Returns an error if the position is not finite.
+    pub fn add_free_point(
+        &mut self,
+        caption: impl ToString,
+        (x, y): (f64, f64),
+    ) -> Result<Var<Point>, NonFiniteCoords> {
+        let coords = Coords::try_xy(x, y)?;
+        let label = self.next_label();
+        self.labels().defined.insert(label.clone());
 
         self.data
             .construction
             .items
-            .push(ConstructionItem::Element(Element {
+            .push(ConstructionItem::Element(Box::new(Element {
                 type_: ElementType::Point,
                 label: label.clone(),
                 caption: Some(caption.to_string().into()),
-                coords: Some(Coords::xy(x, y)),
-                ..point.0.style.to_element()
-            }));
+                coords: Some(coords),
+                ..Point::free().to_element()
+            })));
 
-        Point::var(label)
+        Ok(Point::var(label))
     }
 
-    /// Make an expression into a variable without making it an element.
-    pub fn var<T: Expr>(&mut self, expr: T) -> Var<T::Target> {
-        let label = self.next_label();
-        let expr = expr.into();
+    /// Push an expression item, referencing already labeled subexpressions by their labels.
+    /// Free objects are never referenced this way: they can be moved or animated,
+    /// and that must not drag along objects that only happened to look the same.
+    fn push_expression(&mut self, type_: ElementType, label: &str, exp: &str) {
+        let labels = self.labels();
+        let deduped = dedup_expression(exp, &labels.expressions);
+
+        if labels.is_dependent(&deduped) {
+            labels
+                .expressions
+                .insert(deduped.clone(), label.to_string());
+        }
+
+        labels.defined.insert(label.to_string());
 
         self.data
             .construction
             .items
             .push(ConstructionItem::Expression(raw::Expression {
-                type_: T::get_type(),
-                label: label.clone(),
-                exp: expr.expr.as_ref().clone(),
+                type_,
+                label: label.to_string(),
+                exp: deduped,
             }));
+    }
+
+    /// Add a slider going from `min` to `max` in steps of `step`.
+    /// It's labeled with its caption and current value, like `speed = 3`.
+    pub fn add_slider(
+        &mut self,
+        caption: impl ToString,
+        value: f64,
+        (min, max): (f64, f64),
+        step: f64,
+    ) -> Var<Numeric> {
+        let label = self.next_label();
+
+        // A plain real literal, so that GeoGebra treats it as a free number.
+        self.push_expression(ElementType::Numeric, &label, &value.to_string());
+
+        self.data
+            .construction
+            .items
+            .push(ConstructionItem::Element(Box::new(Element {
+                type_: ElementType::Numeric,
+                label: label.clone(),
+                caption: Some(caption.to_string().into()),
+                label_mode: LabelMode::CaptionAndValue.into(),
+                show: Show {
+                    object: true,
+                    label: true,
+                    ev: None,
+                },
+                value: Some(value.into()),
+                slider: Some(raw::Slider {
+                    min,
+                    max,
+                    width: 200.0,
+                    fixed: false,
+                    horizontal: true,
+                    show_algebra: true,
+                }),
+                animation: Some(raw::Animation {
+                    step,
+                    speed: 1.0,
+                    playing: false,
+                }),
+                ..Style::default().to_element()
+            })));
+
+        Numeric::var(label)
+    }
+
+    /// Draw the slope field of a differential equation `y' = f(x, y)`,
+    /// with `density` segments along each axis.
+    pub fn add_slope_field(&mut self, f: impl Into<Function>, density: u32) -> Var<Locus> {
+        self.add(Locus::slope_field(f, density), "")
+    }
+
+    /// Set which parts of an object are shown.
+    pub fn set_visible<T>(&mut self, var: &Var<T>, show: Show) {
+        if let Some(element) = self.element_mut(&var.0) {
+            element.show = show;
+        }
+    }
+
+    /// Set the color of every element matching a predicate.
+    pub fn recolor_where(&mut self, pred: impl Fn(&Element) -> bool, color: ObjColorType) {
+        for item in &mut self.data.construction.items {
+            if let ConstructionItem::Element(element) = item {
+                if pred(element) {
+                    element.obj_color = Some(color);
+                }
+            }
+        }
+    }
+
+    /// Set what is displayed in place of an object's label.
+    pub fn set_label_mode<T>(&mut self, var: &Var<T>, mode: LabelMode) {
+        if let Some(element) = self.element_mut(&var.0) {
+            element.label_mode = mode.into();
+        }
+    }
+
+    /// Pin an object to a position on the screen, in pixels. Meant for texts, sliders and buttons.
+    pub fn set_screen_position<T>(&mut self, var: &Var<T>, x: i32, y: i32) {
+        if let Some(element) = self.element_mut(&var.0) {
+            element.absolute_screen_location = Some(ScreenLocation { x, y });
+        }
+    }
+
+    /// Make an expression into a variable without making it an element.
+    pub fn var<T: Expr>(&mut self, expr: T) -> Var<T::Target> {
+        let label = self.next_label();
+        let expr = expr.into();
+
+        self.push_expression(T::get_type(), &label, &expr.expr.to_string());
 
         self.data
             .construction
             .items
-            .push(ConstructionItem::Element(Element {
+            .push(ConstructionItem::Element(Box::new(Element {
                 type_: T::get_type(),
                 label: label.clone(),
                 caption: None,
                 show: Show::none(),
                 ..expr.style.to_element()
-            }));
+            })));
 
         T::var(label)
     }
 }
 
+/// A just-added object, with setters for its style
+pub struct StyledVar<'a, T> {
+    ggb: &'a mut Geogebra,
+    var: Var<T>,
+}
+
+impl<T> StyledVar<'_, T> {
+    /// Set the object's color
+    #[must_use]
+    pub fn color(self, r: u8, g: u8, b: u8) -> Self {
+        if let Some(element) = self.ggb.element_mut(&self.var.0) {
+            element.obj_color = Some(ObjColorType {
+                r,
+                g,
+                b,
+                alpha: None,
+            });
+        }
+
+        self
+    }
+
+    /// Set the object's size: point size for points, line thickness otherwise
+    #[must_use]
+    pub fn size(self, size: u8) -> Self {
+        if let Some(element) = self.ggb.element_mut(&self.var.0) {
+            if let ElementType::Point = element.type_ {
+                element.point_size = Some(size.into());
+            } else {
+                element
+                    .line_style
+                    .get_or_insert_with(LineStyle::default)
+                    .thickness = Some(size.into());
+            }
+        }
+
+        self
+    }
+
+    /// Wether to display the object's label
+    #[must_use]
+    pub fn show_label(self, v: bool) -> Self {
+        if let Some(element) = self.ggb.element_mut(&self.var.0) {
+            element.show.label = v;
+        }
+
+        self
+    }
+
+    /// Finish styling and get the variable
+    #[must_use]
+    pub fn var(self) -> Var<T> {
+        self.var
+    }
+}
+
 impl Default for Geogebra {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read, Write};
+
+    use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+    use super::*;
+
+    /// Pack a `geogebra.xml` into a ggb file.
+    fn ggb_file(xml: &str) -> Vec<u8> {
+        let mut file = ZipWriter::new(Cursor::new(Vec::new()));
+        file.start_file("geogebra.xml", SimpleFileOptions::default())
+            .unwrap();
+        file.write_all(xml.as_bytes()).unwrap();
+        file.finish().unwrap().into_inner()
+    }
+
+    /// Get the `geogebra.xml` out of a ggb file.
+    fn xml_of(ggb: &[u8]) -> String {
+        let mut file = ZipArchive::new(Cursor::new(ggb)).unwrap();
+        let mut xml = String::new();
+        file.by_name("geogebra.xml")
+            .unwrap()
+            .read_to_string(&mut xml)
+            .unwrap();
+        xml
+    }
+
+    fn written(ggb: &Geogebra) -> Vec<u8> {
+        let mut file = Cursor::new(Vec::new());
+        ggb.write(&mut file).unwrap();
+        file.into_inner()
+    }
+
+    /// Expression text of the item defining `label`.
+    fn exp_of<'a>(ggb: &'a Geogebra, label: &str) -> &'a str {
+        ggb.raw()
+            .construction
+            .items
+            .iter()
+            .find_map(|item| match item {
+                ConstructionItem::Expression(expression) if expression.label == label => {
+                    Some(expression.exp.as_str())
+                }
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn dedup_skips_string_literals() {
+        let mut labels = HashMap::new();
+        labels.insert(String::from("Midpoint(A, B)"), String::from("M"));
+
+        assert_eq!(
+            dedup_expression(r#"Text("Midpoint(A, B) is here", Midpoint(A, B))"#, &labels),
+            r#"Text("Midpoint(A, B) is here", M)"#
+        );
+        assert_eq!(
+            dedup_expression(r#""(" + Midpoint(A, B)"#, &labels),
+            r#""(" + M"#
+        );
+    }
+
+    #[test]
+    fn dedup_nested_subexpressions() {
+        let mut ggb = Geogebra::new();
+        let a = ggb.add(Point::from((1, 2)), "");
+        let b = ggb.add(Point::from((3, 4)), "");
+        let m = ggb.add(Point::midpoint(&a, &b), "");
+        let _ = ggb.add(Segment::new(Point::midpoint(&a, &b), (0, 0)), "");
+        let _ = ggb.add(Text::new("Midpoint(elem0, elem1)"), "");
+
+        assert_eq!(
+            exp_of(&ggb, "elem3"),
+            format!("Segment({}, (real(0 + 0i), real(0 + 0i)))", m.0)
+        );
+        assert!(exp_of(&ggb, "elem4").contains("\"Midpoint(elem0, elem1)\""));
+    }
+
+    #[test]
+    fn dedup_skips_free_objects() {
+        let mut ggb = Geogebra::new();
+        let x = ggb.add_slider("x", 1.0, (0.0, 2.0), 0.1);
+        let _ = ggb.add(Point::from((1, 2)), "");
+        let _ = ggb.add(Segment::new((1, 2), (x.0.as_str(), 0)), "");
+
+        assert!(exp_of(&ggb, "elem2").starts_with("Segment((real(1 + 0i), real(2 + 0i)), "));
+
+        let frames = ggb.animate(&x, &[0.5]).unwrap();
+        assert!(xml_of(&frames[0]).contains(r#"exp="Segment((real(1 + 0i), real(2 + 0i)), "#));
+    }
+
+    #[test]
+    fn animate_rejects_bad_input() {
+        let mut ggb = Geogebra::new();
+        let x = ggb.add_slider("x", 1.0, (0.0, 2.0), 0.1);
+
+        assert!(ggb.animate(&x, &[f64::NAN]).is_err());
+        assert!(Geogebra::new().animate(&x, &[1.0]).is_err());
+        assert_eq!(ggb.animate(&x, &[0.0, 1.0]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn from_raw_dedups_like_push_expression() {
+        let mut ggb = Geogebra::new();
+        let a = ggb.add(Point::from((1, 2)), "");
+        let b = ggb.add(Point::from((3, 4)), "");
+        let _ = ggb.add(Point::midpoint(&a, &b), "");
+
+        let mut read = Geogebra::from_raw(ggb.raw().clone());
+        let _ = read.add(Segment::new(Point::midpoint(&a, &b), (0, 0)), "");
+
+        assert!(exp_of(&read, "elem3").starts_with("Segment(elem2, "));
+    }
+
+    #[test]
+    fn dedup_follows_raw_changes() {
+        let mut ggb = Geogebra::new();
+        let a = ggb.add(Point::from((1, 2)), "");
+        let b = ggb.add(Point::from((3, 4)), "");
+        let _ = ggb.add(Point::midpoint(&a, &b), "");
+
+        // Rename the midpoint.
+        for item in &mut ggb.raw_mut().construction.items {
+            match item {
+                ConstructionItem::Expression(expression) if expression.label == "elem2" => {
+                    expression.label = String::from("M");
+                }
+                ConstructionItem::Element(element) if element.label == "elem2" => {
+                    element.label = String::from("M");
+                }
+                _ => (),
+            }
+        }
+
+        let _ = ggb.add(Segment::new(Point::midpoint(&a, &b), (0, 0)), "");
+        assert!(exp_of(&ggb, "elem3").starts_with("Segment(M, "));
+
+        // Remove it altogether.
+        ggb.raw_mut().construction.items.retain(|item| match item {
+            ConstructionItem::Expression(expression) => expression.label != "M",
+            ConstructionItem::Element(element) => element.label != "M",
+            ConstructionItem::Command(_) => true,
+        });
+
+        let _ = ggb.add(Segment::new(Point::midpoint(&a, &b), (0, 0)), "");
+        assert!(exp_of(&ggb, "elem4").starts_with("Segment(Midpoint(elem0, elem1), "));
+    }
+
+    #[test]
+    fn numeric_order_agrees_with_equality() {
+        let mut ggb = Geogebra::new();
+        let x = Numeric::from(&ggb.add_slider("x", 1.0, (0.0, 2.0), 0.1));
+
+        assert!(x == x.clone());
+        assert_eq!(x.partial_cmp(&x.clone()), Some(Ordering::Equal));
+        assert_eq!(x.partial_cmp(&Numeric::from(1.0)), None);
+        assert_eq!(
+            Numeric::from(1.0).partial_cmp(&Numeric::from(2.0)),
+            Some(Ordering::Less)
+        );
+    }
+
+    #[test]
+    fn folds_literals() {
+        assert_eq!((Numeric::from(2.0) + 3.0).as_f64(), Some(5.0));
+        assert_eq!((Numeric::from(2.0) - 3.0).as_f64(), Some(-1.0));
+        assert_eq!((Numeric::from(2.0) * 3.0).as_f64(), Some(6.0));
+        assert_eq!((Numeric::from(3.0) / 2.0).as_f64(), Some(1.5));
+    }
+
+    #[test]
+    fn keeps_non_literals() {
+        let mut ggb = Geogebra::new();
+        let x = ggb.add_slider("x", 1.0, (0.0, 2.0), 0.1);
+        let sum = Numeric::from(&x) + 2.0;
+
+        assert_eq!(sum.as_f64(), None);
+        assert_eq!(sum.0.expr.to_string(), format!("({}) + (2 + 0i)", x.0));
+    }
+
+    #[test]
+    fn does_not_fold_non_finite_results() {
+        let quotient = Numeric::from(1.0) / 0.0;
+
+        assert_eq!(quotient.as_f64(), None);
+        assert_eq!(quotient.0.expr.to_string(), "(1 + 0i) / (0 + 0i)");
+    }
+
+    #[test]
+    fn rem_euclid_with_negatives() {
+        // The emitted formula, evaluated the way GeoGebra does.
+        let formula = |a: f64, b: f64| a - b.abs() * (a / b.abs()).floor();
+
+        for (a, b, rem) in [(7, 3, 1), (-7, 3, 2), (7, -3, 1), (-7, -3, 2)] {
+            assert_eq!(
+                Numeric::from(a).rem_euclid(b).as_f64(),
+                Some(rem.into()),
+                "{a} rem_euclid {b}"
+            );
+            assert_eq!(
+                formula(a.into(), b.into()),
+                rem.into(),
+                "{a} rem_euclid {b}"
+            );
+        }
+
+        assert_eq!(
+            Numeric::from("a").rem_euclid(-3).0.expr.to_string(),
+            "real(a) - abs(real(-3 + 0i)) floor(real(a) / abs(real(-3 + 0i)))"
+        );
+    }
+
+    #[test]
+    fn truncated_mod_with_negatives() {
+        // The emitted formula, evaluated the way GeoGebra does.
+        let formula = |a: f64, b: f64| a - b * (a / b).signum() * (a / b).abs().floor();
+
+        for (a, b, rem) in [(7, 3, 1), (-7, 3, -1), (7, -3, 1), (-7, -3, -1)] {
+            assert_eq!(
+                Numeric::from(a).truncated_mod(b).as_f64(),
+                Some(rem.into()),
+                "{a} truncated_mod {b}"
+            );
+            assert_eq!(
+                formula(a.into(), b.into()),
+                rem.into(),
+                "{a} truncated_mod {b}"
+            );
+        }
+
+        assert_eq!(
+            Numeric::from("a").truncated_mod(-3).0.expr.to_string(),
+            "real(a) - real(-3 + 0i) sgn(real(a) / real(-3 + 0i)) \
+             floor(abs(real(a) / real(-3 + 0i)))"
+        );
+        assert_eq!(Numeric::from(7).truncated_mod(0).as_f64(), None);
+    }
+
+    #[test]
+    fn vars_convert_to_their_types() {
+        let mut ggb = Geogebra::new();
+        let a = ggb.add(Point::from((0, 0)), "");
+        let b = ggb.add(Point::from((1, 1)), "");
+        let number = ggb.add_slider("n", 2.0, (0.0, 5.0), 1.0);
+        let list = ggb.add_list([Numeric::from(1.0), Numeric::from(2.0)], "");
+        let ray = ggb.add(Ray::new(&a, &b), "");
+        let segment = ggb.add(Segment::new(&a, &b), "");
+        let angle = ggb.add(Angle::new(&a, &b, (1, 0)), "");
+
+        assert_eq!(Numeric::from(&number).0.expr.to_string(), *number.0);
+        assert_eq!(Numeric::from(number.clone()).0.expr.to_string(), *number.0);
+        assert_eq!(List::from(&list).0.expr.to_string(), *list.0);
+        assert_eq!(List::from(list.clone()).0.expr.to_string(), *list.0);
+        assert_eq!(Ray::from(&ray).0.expr.to_string(), *ray.0);
+        assert_eq!(Ray::from(ray.clone()).0.expr.to_string(), *ray.0);
+        assert_eq!(Segment::from(&segment).0.expr.to_string(), *segment.0);
+        assert_eq!(
+            Segment::from(segment.clone()).0.expr.to_string(),
+            *segment.0
+        );
+        assert_eq!(Numeric::from(&angle).0.expr.to_string(), *angle.0);
+        assert_eq!(Numeric::from(angle.clone()).0.expr.to_string(), *angle.0);
+    }
+
+    #[test]
+    fn abs_and_modulus_of_complex_literal() {
+        let z = Numeric::complex(3, -4);
+
+        assert_eq!(
+            z.clone().abs().0.expr.to_string(),
+            "abs(real((3 + 0i) + (-4 + 0i)i))"
+        );
+        assert_eq!(z.modulus().0.expr.to_string(), "abs((3 + 0i) + (-4 + 0i)i)");
+    }
+
+    #[test]
+    fn streams_large_construction() {
+        let mut ggb = Geogebra::new();
+        let mut previous = ggb.add(Point::from((0, 0)), "");
+
+        for i in 1..3_000 {
+            let point = ggb.add(Point::from((i, i % 7)), "");
+            let _ = ggb.add(Segment::new(&previous, &point), "");
+            previous = point;
+        }
+
+        let mut streamed = Vec::new();
+        ggb.serialize_into(&mut streamed, false).unwrap();
+
+        assert_eq!(
+            String::from_utf8(streamed).unwrap(),
+            quick_xml::se::to_string(ggb.raw()).unwrap()
+        );
+
+        let xml = xml_of(&written(&ggb));
+        assert!(xml.contains(&format!(r#"label="{}""#, previous.0)));
+    }
+
+    #[test]
+    fn ignores_non_finite_opacity() {
+        let mut line = Line::new((0, 0), (1, 1));
+        line.set_opacity(f64::NAN);
+        assert_eq!(
+            line.0.style.line_style.and_then(|style| style.opacity),
+            None
+        );
+
+        line.set_opacity(2.0);
+        assert_eq!(
+            line.0.style.line_style.and_then(|style| style.opacity),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn ignores_non_finite_color_alpha() {
+        let mut circle = Conic::circle((0, 0), 1);
+        circle.set_color_alpha(255, 0, 0, f64::NAN);
+        assert!(circle.0.style.color.is_none());
+
+        let mut triangle = Triangle::new((0, 0), (1, 0), (0, 1));
+        triangle.set_color_alpha(255, 0, 0, f64::INFINITY);
+        assert!(triangle.0 .0.style.color.is_none());
+
+        let mut region = Line::new((0, 0), (1, 1)).half_plane_above();
+        region.set_color_alpha(255, 0, 0, -1.0);
+        assert_eq!(
+            region.0.style.color.and_then(|color| color.alpha),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn user_function_calls_are_references() {
+        let expression = |label: &str, exp: &str| {
+            ConstructionItem::Expression(raw::Expression {
+                type_: ElementType::Function,
+                label: label.to_string(),
+                exp: exp.to_string(),
+            })
+        };
+
+        let mut data = Geogebra::new().raw().clone();
+        data.construction.items = vec![
+            expression("g", "g(x) = f(x) + Segment(A, B)"),
+            expression("f", "f(x) = x^2"),
+        ];
+        let mut ggb = Geogebra::from_raw(data);
+
+        let errors = ggb.validate().unwrap_err();
+        assert!(errors.contains(&ValidationError::UsedBeforeDefinition {
+            label: String::from("g"),
+            reference: String::from("f"),
+        }));
+        assert!(!errors.iter().any(|error| matches!(
+            error,
+            ValidationError::DanglingReference { reference, .. } if reference == "Segment"
+        )));
+
+        ggb.sort_dependencies().unwrap();
+        assert_eq!(exp_of(&ggb, "f"), "f(x) = x^2");
+        let ConstructionItem::Expression(first) = &ggb.raw().construction.items[0] else {
+            panic!("expected an expression");
+        };
+        assert_eq!(first.label, "f");
+    }
+
+    #[test]
+    fn sorts_texts_after_their_anchors() {
+        let mut ggb = Geogebra::new();
+        let a = ggb.add(Point::from((1, 2)), "");
+        let text = ggb.add(Text::new("A").anchor(&a), "");
+
+        ggb.raw_mut().construction.items.rotate_left(2);
+        ggb.sort_dependencies().unwrap();
+
+        let labels: Vec<_> = ggb
+            .raw()
+            .construction
+            .items
+            .iter()
+            .map(|item| match item {
+                ConstructionItem::Expression(expression) => expression.label.as_str(),
+                ConstructionItem::Element(element) => element.label.as_str(),
+                ConstructionItem::Command(_) => "",
+            })
+            .collect();
+        assert_eq!(labels, [a.0.as_str(), &a.0, &text.0, &text.0]);
+    }
+
+    #[test]
+    fn index_variable_is_reserved() {
+        let mut ggb = Geogebra::new();
+
+        assert!(matches!(
+            ggb.add_named(Point::from((0, 0)), INDEX_VARIABLE, ""),
+            Err(LabelError::Invalid {
+                rule: LabelRule::Reserved,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn edges_write_the_polygon_once() {
+        let mut ggb = Geogebra::new();
+        let edges = Polygon::new([(0, 0), (1, 0), (0, 1)]).edges();
+        let exp = edges.0.expr.to_string();
+
+        assert_eq!(exp.matches("Polygon(").count(), 1);
+
+        let _ = ggb.add(edges, "");
+        assert_eq!(ggb.validate(), Ok(()));
+    }
+
+    #[test]
+    fn signed_area_writes_the_polygon_once() {
+        let area = Polygon::new([(0, 0), (1, 0), (0, 1)]).signed_area();
+        let exp = area.0.expr.to_string();
+
+        assert_eq!(exp.matches("Polygon(").count(), 1);
+        assert!(referenced_labels(&exp, |_| false).is_empty());
+    }
+
+    #[test]
+    fn label_prefix_must_make_valid_labels() {
+        let mut ggb = Geogebra::new();
+
+        for prefix in ["", "a b", "1p"] {
+            assert!(ggb.set_label_prefix(prefix).is_err(), "{prefix:?}");
+        }
+
+        ggb.set_label_prefix("x").unwrap();
+        assert_eq!(*ggb.add(Point::from((0, 0)), "").0, "x0");
+    }
+
+    #[test]
+    fn transforms_keep_style() {
+        let color = |style: &Style| style.color.map(|color| (color.r, color.g, color.b));
+
+        let mut point = Point::from((1, 2));
+        point.set_color(1, 2, 3);
+        let mut line = Line::new((0, 0), (1, 1));
+        line.set_color(4, 5, 6);
+
+        assert_eq!(color(&point.clone().reflect_x().0.style), Some((1, 2, 3)));
+        assert_eq!(
+            color(&Point::reflect_over_point(point.clone(), (0, 0)).0.style),
+            Some((1, 2, 3))
+        );
+        assert_eq!(color(&point.rotate(1.0, (0, 0)).0.style), Some((1, 2, 3)));
+        assert_eq!(
+            color(&line.reflect(Line::new((0, 0), (1, 0))).0.style),
+            Some((4, 5, 6))
+        );
+    }
+
+    #[test]
+    fn weighted_centroid_needs_weights() {
+        let no_points: &[(Point, f64)] = &[];
+
+        assert!(Point::weighted_centroid(no_points).is_none());
+        assert!(Point::weighted_centroid(&[((0, 0), 1.0), ((1, 1), -1.0)]).is_none());
+        assert!(Point::weighted_centroid(&[((0, 0), 1.0), ((1, 1), 2.0)]).is_some());
+    }
+
+    #[test]
+    fn reads_old_format() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<geogebra format="4.2">
+<construction title="" author="" date="">
+<expression label="A" exp="(1, 2)" type="point"/>
+<element type="point" label="A">
+<show object="true" label="true"/>
+<objColor r="0" g="0" b="255" alpha="0.0"/>
+<coords x="1.0" y="2.0" z="1.0"/>
+</element>
+<command name="Midpoint">
+<input a0="A" a1="(3, 4)"/>
+<output a0="M"/>
+</command>
+</construction>
+</geogebra>"#;
+
+        let ggb = Geogebra::read(Cursor::new(ggb_file(xml))).unwrap();
+
+        assert_eq!(ggb.format(), "4.2");
+        assert_eq!(ggb.len(), 3);
+
+        let ConstructionItem::Command(command) = &ggb.raw().construction.items[2] else {
+            panic!("expected a command");
+        };
+        assert_eq!(command.input.attrs, ["A", "(3, 4)"]);
+        assert_eq!(command.output.attrs, ["M"]);
+    }
+
+    #[test]
+    fn keeps_point_size_and_style() {
+        let xml = r#"<geogebra format="5.0"><construction>
+<expression type="point" label="A" exp="(1, 2)"/>
+<element type="point" label="A"><show object="true" label="true"/><pointSize val="7"/><pointStyle val="2"/></element>
+</construction></geogebra>"#;
+
+        let ggb = Geogebra::read(Cursor::new(ggb_file(xml))).unwrap();
+        let rewritten = xml_of(&written(&ggb));
+
+        assert!(rewritten.contains(r#"<pointSize val="7"/>"#));
+        assert!(rewritten.contains(r#"<pointStyle val="2"/>"#));
+    }
+
+    #[test]
+    fn write_read_write_round_trip() {
+        let mut ggb = Geogebra::new();
+        let a = ggb.add(Point::from((1, 2)), "A");
+        let b = ggb.add_free_point("B", (3.0, 1.0)).unwrap();
+        let mut segment = Segment::new(&a, &b);
+        segment.set_dashed();
+        let _ = ggb.add(segment, "");
+        let mut circle = Conic::circle(&a, 2);
+        circle.set_color(255, 0, 0);
+        let _ = ggb.add(circle, "");
+
+        let first = written(&ggb);
+        let read = Geogebra::read(Cursor::new(first.as_slice())).unwrap();
+        let second = written(&read);
+
+        assert_eq!(xml_of(&first), xml_of(&second));
+    }
+
+    #[test]
+    fn range_variables_are_validated() {
+        let sum = Numeric::sum_range(|k| Numeric::from(1.0) / k, "k", 1.0, 100.0).unwrap();
+        assert_eq!(
+            sum.0.expr.to_string(),
+            "Sum((1 + 0i) / (k), k, real(1 + 0i), real(100 + 0i))"
+        );
+
+        for var in ["x", INDEX_VARIABLE, "2k", ""] {
+            assert!(matches!(
+                Numeric::sum_range(|k| k.into(), var, 1.0, 2.0),
+                Err(LabelError::Invalid { .. })
+            ));
+            assert!(matches!(
+                Numeric::product_range(|k| k.into(), var, 1.0, 2.0),
+                Err(LabelError::Invalid { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn texts_are_positioned_by_coords() {
+        let mut ggb = Geogebra::new();
+        let text = ggb.add_text(Text::new("hello"), (1.5, -2.0)).unwrap();
+
+        let xml = xml_of(&written(&ggb));
+        assert!(
+            xml.contains(r#"<startPoint x="1.5" y="-2" z="1"/>"#),
+            "{xml}"
+        );
+        assert_eq!(*text.0, "elem0");
+
+        assert!(ggb.add_text(Text::new("nan"), (f64::NAN, 0.0)).is_err());
+        assert_eq!(ggb.len(), 2);
+    }
+}
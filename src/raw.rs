@@ -1,8 +1,12 @@
 //! Raw GeoGebra structures.
 
-use std::marker::PhantomData;
+use std::{fmt, marker::PhantomData, str::FromStr};
 
-use serde::{de::Visitor, ser::SerializeMap, Deserialize, Serialize};
+use serde::{
+    de::{IgnoredAny, Visitor},
+    ser::SerializeMap,
+    Deserialize, Serialize,
+};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
 /// Top-level element representing a Geogebra workspace
@@ -13,19 +17,162 @@ pub struct Geogebra {
     /// if it's not here. Library was tested with 5.0
     #[serde(rename = "@format")]
     pub format: String,
-    /// Application to load this file in.
-    #[serde(rename = "@app")]
+    /// Application to load this file in. Missing in files from older versions.
+    #[serde(rename = "@app", default)]
     pub app: String,
-    /// Subapplication to load this file in.
-    #[serde(rename = "@subApp")]
+    /// Subapplication to load this file in. Missing in files from older versions.
+    #[serde(rename = "@subApp", default)]
     pub sub_app: String,
+    /// Settings of the graphics views
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub euclidian_view: Vec<EuclidianView>,
+    /// Settings of the computation kernel
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kernel: Option<Kernel>,
     /// The contained construction
     pub construction: Construction,
 }
 
+/// Settings of the computation kernel
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Kernel {
+    /// Number of decimal places values are rounded to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<Val<u8>>,
+    /// Number of significant figures values are rounded to. Takes precedence over `decimals`
+    #[serde(rename = "significantfigures", skip_serializing_if = "Option::is_none")]
+    pub significant_figures: Option<Val<u8>>,
+}
+
+/// Settings of a graphics view
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EuclidianView {
+    /// Which graphics view this is. Absent for the first one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub view_number: Option<ViewNumber>,
+    /// Size of the view, in pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<Size>,
+    /// Placement of the coordinate system in the view
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coord_system: Option<CoordSystem>,
+    /// General settings of the view
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ev_settings: Option<EvSettings>,
+    /// Background color of the view
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bg_color: Option<ObjColorType>,
+    /// Settings of the axes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub axis: Vec<Axis>,
+    /// Spacing of the grid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grid: Option<Grid>,
+}
+
+/// Number of a graphics view
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ViewNumber {
+    /// The number, starting at 1
+    #[serde(rename = "@viewNo")]
+    pub view_no: u8,
+}
+
+/// Size of a graphics view, in pixels
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Size {
+    /// Width of the view
+    #[serde(rename = "@width")]
+    pub width: u32,
+    /// Height of the view
+    #[serde(rename = "@height")]
+    pub height: u32,
+}
+
+/// Placement of the coordinate system in a graphics view
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CoordSystem {
+    /// Screen X coordinate of the origin, in pixels
+    #[serde(rename = "@xZero")]
+    pub x_zero: f64,
+    /// Screen Y coordinate of the origin, in pixels
+    #[serde(rename = "@yZero")]
+    pub y_zero: f64,
+    /// Pixels per unit on the X axis
+    #[serde(rename = "@scale")]
+    pub scale: f64,
+    /// Pixels per unit on the Y axis
+    #[serde(rename = "@yscale")]
+    pub yscale: f64,
+}
+
+/// General settings of a graphics view
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct EvSettings {
+    /// Whether to show the axes
+    #[serde(rename = "@axes", skip_serializing_if = "Option::is_none")]
+    pub axes: Option<bool>,
+    /// Whether to show the grid
+    #[serde(rename = "@grid", skip_serializing_if = "Option::is_none")]
+    pub grid: Option<bool>,
+    /// Type of the grid
+    #[serde(rename = "@gridType", skip_serializing_if = "Option::is_none")]
+    pub grid_type: Option<GridType>,
+}
+
+/// Type of the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum GridType {
+    /// Square grid
+    Cartesian = 0,
+    /// Triangular grid
+    Isometric = 1,
+    /// Concentric circles and rays
+    Polar = 2,
+    /// Square grid with minor lines
+    CartesianWithSubgrid = 3,
+}
+
+/// Spacing of the grid
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Grid {
+    /// Horizontal distance between lines (radial for polar grids)
+    #[serde(rename = "@distX")]
+    pub dist_x: f64,
+    /// Vertical distance between lines
+    #[serde(rename = "@distY")]
+    pub dist_y: f64,
+}
+
+/// Settings of a single axis
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Axis {
+    /// Which axis this is. 0 for X, 1 for Y
+    #[serde(rename = "@id")]
+    pub id: u8,
+    /// Label displayed next to the axis
+    #[serde(rename = "@label", skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Unit displayed next to the axis' numbers
+    #[serde(rename = "@unitLabel", skip_serializing_if = "Option::is_none")]
+    pub unit_label: Option<String>,
+}
+
 /// The construction contained in the workspace
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Construction {
+    /// Title of the construction
+    #[serde(rename = "@title", skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Author of the construction
+    #[serde(rename = "@author", skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Date of the construction, in any format
+    #[serde(rename = "@date", skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
     /// Construction's items
     #[serde(rename = "$value")]
     pub items: Vec<ConstructionItem>,
@@ -36,7 +183,7 @@ pub struct Construction {
 #[serde(rename_all = "camelCase")]
 pub enum ConstructionItem {
     /// An element of the construction.
-    Element(Element),
+    Element(Box<Element>),
     /// A construction command.
     Command(Command),
     /// An expression
@@ -54,17 +201,101 @@ pub struct Element {
     #[serde(rename = "@label")]
     pub label: String,
     /// The element's caption
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub caption: Option<Val<String>>,
     /// What should be displayed in place of the label
+    #[serde(default)]
     pub label_mode: Val<LabelMode>,
     /// Which parts of the element should be shown
+    #[serde(default)]
     pub show: Show,
     /// The element's coordinates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub coords: Option<Coords>,
     /// How to draw the line, if this is a line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub line_style: Option<LineStyle>,
+    /// Size of the point, if this is a point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub point_size: Option<Val<u8>>,
+    /// Shape of the point, if this is a point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub point_style: Option<Val<u8>>,
     /// Color of this object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub obj_color: Option<ObjColorType>,
+    /// Position on the screen, in pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub absolute_screen_location: Option<ScreenLocation>,
+    /// Font of the label
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font: Option<Font>,
+    /// Point this object is anchored to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_point: Option<StartPoint>,
+    /// Whether this text is a LaTeX formula
+    #[serde(rename = "isLaTeX", skip_serializing_if = "Option::is_none")]
+    pub is_latex: Option<Val<bool>>,
+    /// Value of a free number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Val<f64>>,
+    /// Slider settings, if this number is a slider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slider: Option<Slider>,
+    /// Animation settings of a slider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animation: Option<Animation>,
+}
+
+/// Slider settings of a free number
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Slider {
+    /// Lowest value
+    #[serde(rename = "@min")]
+    pub min: f64,
+    /// Highest value
+    #[serde(rename = "@max")]
+    pub max: f64,
+    /// Length of the slider, in pixels
+    #[serde(rename = "@width")]
+    pub width: f64,
+    /// Whether the slider can't be moved around
+    #[serde(rename = "@fixed")]
+    pub fixed: bool,
+    /// Whether the slider is horizontal
+    #[serde(rename = "@horizontal")]
+    pub horizontal: bool,
+    /// Whether the slider is shown in the algebra view
+    #[serde(rename = "@showAlgebra")]
+    pub show_algebra: bool,
+}
+
+/// Animation settings of a slider
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Animation {
+    /// Increment of the value
+    #[serde(rename = "@step")]
+    pub step: f64,
+    /// Speed of the animation
+    #[serde(rename = "@speed")]
+    pub speed: f64,
+    /// Whether the animation is running
+    #[serde(rename = "@playing")]
+    pub playing: bool,
+}
+
+/// Font of an element's label
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Font {
+    /// Whether to use a serif font
+    #[serde(rename = "@serif")]
+    pub serif: bool,
+    /// Size relative to the default font size
+    #[serde(rename = "@size")]
+    pub size: i8,
+    /// Bit flags: 1 for bold, 2 for italic
+    #[serde(rename = "@style")]
+    pub style: u8,
 }
 
 /// Type of an element
@@ -78,40 +309,145 @@ pub enum ElementType {
     Conic,
     Ray,
     List,
+    Vector,
+    Angle,
+    Polygon,
+    FunctionNVar,
+    Function,
+    Locus,
+    CurveCartesian,
+    Text,
+    #[serde(rename = "implicitpoly")]
+    ImplicitPoly,
+}
+
+impl ElementType {
+    /// Get the Geogebra token of this type
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Point => "point",
+            Self::Segment => "segment",
+            Self::Line => "line",
+            Self::Numeric => "numeric",
+            Self::Conic => "conic",
+            Self::Ray => "ray",
+            Self::List => "list",
+            Self::Vector => "vector",
+            Self::Angle => "angle",
+            Self::Polygon => "polygon",
+            Self::FunctionNVar => "functionNVar",
+            Self::Function => "function",
+            Self::Locus => "locus",
+            Self::CurveCartesian => "curveCartesian",
+            Self::Text => "text",
+            Self::ImplicitPoly => "implicitpoly",
+        }
+    }
+}
+
+impl FromStr for ElementType {
+    type Err = UnknownElementType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "point" => Self::Point,
+            "segment" => Self::Segment,
+            "line" => Self::Line,
+            "numeric" => Self::Numeric,
+            "conic" => Self::Conic,
+            "ray" => Self::Ray,
+            "list" => Self::List,
+            "vector" => Self::Vector,
+            "angle" => Self::Angle,
+            "polygon" => Self::Polygon,
+            "functionNVar" => Self::FunctionNVar,
+            "function" => Self::Function,
+            "locus" => Self::Locus,
+            "curveCartesian" => Self::CurveCartesian,
+            "text" => Self::Text,
+            "implicitpoly" => Self::ImplicitPoly,
+            _ => return Err(UnknownElementType(s.to_string())),
+        })
+    }
 }
 
+/// Error of parsing an unknown element type token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownElementType(pub String);
+
+impl fmt::Display for UnknownElementType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown element type `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownElementType {}
+
 /// Style of a line
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct LineStyle {
     /// Thickness. 5 by default
-    #[serde(rename = "@thickness")]
+    #[serde(
+        rename = "@thickness",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub thickness: Option<u16>,
     /// Stroke
-    #[serde(rename = "@type")]
+    #[serde(rename = "@type", default, skip_serializing_if = "Option::is_none")]
     pub type_: Option<LineType>,
     /// Opacity of this object
-    #[serde(rename = "@opacity")]
+    #[serde(rename = "@opacity", default, skip_serializing_if = "Option::is_none")]
     pub opacity: Option<f64>,
 }
 
 /// Stroke of a line
-#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr)]
-#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "u16", into = "u16")]
 pub enum LineType {
     /// Solid line
-    Solid = 0,
+    Solid,
     /// Short dashes
-    DashedShort = 10,
+    DashedShort,
     /// Long dashes
-    DashedLong = 15,
+    DashedLong,
     /// Dots
-    Dotted = 20,
+    Dotted,
     /// Dots and dashes
-    DashedDotted = 30,
+    DashedDotted,
+    /// Any other stroke code
+    Other(u16),
+}
+
+impl From<u16> for LineType {
+    fn from(value: u16) -> Self {
+        match value {
+            0 => Self::Solid,
+            10 => Self::DashedShort,
+            15 => Self::DashedLong,
+            20 => Self::Dotted,
+            30 => Self::DashedDotted,
+            v => Self::Other(v),
+        }
+    }
+}
+
+impl From<LineType> for u16 {
+    fn from(value: LineType) -> Self {
+        match value {
+            LineType::Solid => 0,
+            LineType::DashedShort => 10,
+            LineType::DashedLong => 15,
+            LineType::Dotted => 20,
+            LineType::DashedDotted => 30,
+            LineType::Other(v) => v,
+        }
+    }
 }
 
 /// A value in an attribute
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct Val<T> {
     #[serde(rename = "@val")]
     pub val: T,
@@ -124,10 +460,11 @@ impl<T> From<T> for Val<T> {
 }
 
 /// What to display in place of an element's label
-#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr)]
+#[derive(Debug, Clone, Copy, Default, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum LabelMode {
     /// Label
+    #[default]
     Label,
     /// Label = Value
     LabelAndValue,
@@ -143,11 +480,27 @@ pub enum LabelMode {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Show {
     /// Show the object itself.
-    #[serde(rename = "@object")]
+    #[serde(rename = "@object", default = "default_true")]
     pub object: bool,
     /// Show the object's label
-    #[serde(rename = "@label")]
+    #[serde(rename = "@label", default)]
     pub label: bool,
+    /// Bit flags of graphics views: 1 hides the object in the first view,
+    /// 2 shows it in the second one
+    #[serde(rename = "@ev", skip_serializing_if = "Option::is_none")]
+    pub ev: Option<u8>,
+}
+
+impl Default for Show {
+    /// Objects are shown without their label unless stated otherwise.
+    fn default() -> Self {
+        Self::object()
+    }
+}
+
+/// Default of boolean attributes that GeoGebra treats as set when missing
+fn default_true() -> bool {
+    true
 }
 
 impl Show {
@@ -157,6 +510,7 @@ impl Show {
         Self {
             object: true,
             label: false,
+            ev: None,
         }
     }
 
@@ -166,6 +520,7 @@ impl Show {
         Self {
             object: false,
             label: true,
+            ev: None,
         }
     }
 
@@ -175,6 +530,7 @@ impl Show {
         Self {
             object: true,
             label: true,
+            ev: None,
         }
     }
 
@@ -184,6 +540,7 @@ impl Show {
         Self {
             object: false,
             label: false,
+            ev: None,
         }
     }
 }
@@ -208,6 +565,92 @@ impl Coords {
     pub fn xy(x: f64, y: f64) -> Self {
         Self { x, y, z: 1.0 }
     }
+
+    /// Create new coords from X and Y coordinates, rejecting NaN and infinities.
+    ///
+    /// # Errors
+    /// Returns an error if either coordinate is not finite.
+    pub fn try_xy(x: f64, y: f64) -> Result<Self, NonFiniteCoords> {
+        if x.is_finite() && y.is_finite() {
+            Ok(Self::xy(x, y))
+        } else {
+            Err(NonFiniteCoords { x, y })
+        }
+    }
+
+    /// Get the X coordinate
+    #[must_use]
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    /// Get the Y coordinate
+    #[must_use]
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    /// Get the Z coordinate
+    #[must_use]
+    pub fn z(&self) -> f64 {
+        self.z
+    }
+}
+
+/// Anchor of a text or image, either a point expression or fixed coordinates
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StartPoint {
+    /// Expression of the anchor point
+    #[serde(rename = "@exp", skip_serializing_if = "Option::is_none")]
+    pub exp: Option<String>,
+    /// X coordinate
+    #[serde(rename = "@x", skip_serializing_if = "Option::is_none")]
+    pub x: Option<f64>,
+    /// Y coordinate
+    #[serde(rename = "@y", skip_serializing_if = "Option::is_none")]
+    pub y: Option<f64>,
+    /// Z coordinate, 1 for absolute positions
+    #[serde(rename = "@z", skip_serializing_if = "Option::is_none")]
+    pub z: Option<f64>,
+}
+
+impl From<Coords> for StartPoint {
+    fn from(coords: Coords) -> Self {
+        Self {
+            exp: None,
+            x: Some(coords.x),
+            y: Some(coords.y),
+            z: Some(coords.z),
+        }
+    }
+}
+
+/// Error of creating coordinates that GeoGebra can't read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NonFiniteCoords {
+    /// X coordinate
+    pub x: f64,
+    /// Y coordinate
+    pub y: f64,
+}
+
+impl fmt::Display for NonFiniteCoords {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "coordinates ({}, {}) are not finite", self.x, self.y)
+    }
+}
+
+impl std::error::Error for NonFiniteCoords {}
+
+/// Screen coordinates of an element, in pixels
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ScreenLocation {
+    /// X coordinate
+    #[serde(rename = "@x")]
+    pub x: i32,
+    /// Y coordinate
+    #[serde(rename = "@y")]
+    pub y: i32,
 }
 
 /// A construction command.
@@ -274,11 +717,25 @@ impl<'de, T: Deserialize<'de>> Visitor<'de> for IndexedAttrsVisitor<T> {
     {
         let mut attrs = Vec::new();
 
-        while let Some(v) = map.next_value()? {
-            attrs.push(v);
+        while let Some(key) = map.next_key::<String>()? {
+            let index = key
+                .trim_start_matches('@')
+                .strip_prefix('a')
+                .and_then(|index| index.parse::<usize>().ok());
+
+            match index {
+                Some(index) => attrs.push((index, map.next_value()?)),
+                None => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
         }
 
-        Ok(IndexedAttrs { attrs })
+        attrs.sort_by_key(|(index, _)| *index);
+
+        Ok(IndexedAttrs {
+            attrs: attrs.into_iter().map(|(_, attr)| attr).collect(),
+        })
     }
 }
 
@@ -297,15 +754,68 @@ pub struct Expression {
 }
 
 /// Color in Geogebra
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct ObjColorType {
     /// The red channel
-    #[serde(rename = "@r")]
+    #[serde(rename = "@r", default)]
     pub r: u8,
     /// The green channel
-    #[serde(rename = "@g")]
+    #[serde(rename = "@g", default)]
     pub g: u8,
     /// The blue channel
-    #[serde(rename = "@b")]
+    #[serde(rename = "@b", default)]
     pub b: u8,
+    /// Opacity of the object's filling, from 0 to 1
+    #[serde(rename = "@alpha", default, skip_serializing_if = "Option::is_none")]
+    pub alpha: Option<f64>,
+}
+
+impl TryFrom<&str> for ObjColorType {
+    type Error = InvalidColor;
+
+    /// Parse `#RRGGBB` or `rgb(r, g, b)`.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let error = || InvalidColor(value.to_string());
+        let trimmed = value.trim();
+
+        let [r, g, b] = if let Some(hex) = trimmed.strip_prefix('#') {
+            if hex.len() != 6 || !hex.is_ascii() {
+                return Err(error());
+            }
+
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| error());
+            [channel(0)?, channel(2)?, channel(4)?]
+        } else if let Some(args) = trimmed
+            .strip_prefix("rgb(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            let channels = args
+                .split(',')
+                .map(|channel| channel.trim().parse::<u8>().map_err(|_| error()))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            channels.try_into().map_err(|_| error())?
+        } else {
+            return Err(error());
+        };
+
+        Ok(Self {
+            r,
+            g,
+            b,
+            alpha: None,
+        })
+    }
 }
+
+/// Error of parsing a color that is neither `#RRGGBB` nor `rgb(r, g, b)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidColor(pub String);
+
+impl fmt::Display for InvalidColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid color `{}`", self.0)
+    }
+}
+
+impl std::error::Error for InvalidColor {}